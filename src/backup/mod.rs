@@ -13,21 +13,17 @@
 // limitations under the License.
 
 use crate::{
-    rollback::{executor_rollback, rocksdb_rollback, storage_rollback},
-    util::{copy_dir, executor_db_path, read_current_height, storage_db},
+    rollback::{executor_rollback, storage_rollback},
+    storage::{storage_db, storage_db_at, storage_path, Storage},
+    util::{copy_dir, executor_db_path},
 };
 use std::path::{Path, PathBuf};
-use storage_opendal::{config::StorageConfig as OpendalConfig, storager::Storager};
-use storage_rocksdb::{config::StorageConfig as RocksdbConfig, db::DB};
-
-use std::fs;
-use toml::Table;
 
 pub async fn backup(config_path: PathBuf, mut backup_path: PathBuf, height: Option<u64>) {
     // check height
     let storage_db = storage_db(&config_path).await;
     let executor_db_path = executor_db_path(&config_path);
-    let current_height = read_current_height(&storage_db).await;
+    let current_height = storage_db.current_height().await;
     println!("current height: {}", current_height);
     let backup_height = height.unwrap_or(current_height);
     println!("backup height: {}", backup_height);
@@ -54,22 +50,7 @@ pub async fn backup(config_path: PathBuf, mut backup_path: PathBuf, height: Opti
 
     // backup storage data
     // directly copy storage data
-    let s = fs::read_to_string(&config_path)
-        .map_err(|e| println!("read config err: {e}"))
-        .unwrap();
-    let config: Table = s
-        .parse::<Table>()
-        .map_err(|e| println!("config toml parse err: {e}"))
-        .unwrap();
-    let storage_path = if config.contains_key("storage_rocksdb") {
-        let config = RocksdbConfig::new(config_path.to_str().unwrap());
-        config.db_path
-    } else if config.contains_key("storage_opendal") {
-        let config = OpendalConfig::new(config_path.to_str().unwrap());
-        config.data_root
-    } else {
-        panic!("storage config not found")
-    };
+    let storage_path = storage_path(&config_path);
     let storage_backup_path = backup_path.clone().join("chain_data");
     copy_dir(Path::new(&storage_path), &storage_backup_path);
     println!("copy storage chain_data done!");
@@ -84,25 +65,7 @@ pub async fn backup(config_path: PathBuf, mut backup_path: PathBuf, height: Opti
             .unwrap(),
         backup_height,
     );
-    let storage_backup_path = backup_path.clone().join("chain_data");
-
-    if config.contains_key("storage_opendal") {
-        let storage_config = OpendalConfig::default();
-        let write = Storager::build(
-            storage_backup_path.to_str().unwrap(),
-            &storage_config.cloud_storage,
-            &storage_config.exporter,
-            storage_config.l1_capacity,
-            storage_config.l2_capacity,
-        )
-        .await;
-        storage_rollback(&write, backup_height, false).await;
-    } else if config.contains_key("storage_rocksdb") {
-        let storage_config = RocksdbConfig::default();
-        let db = DB::new(storage_backup_path.to_str().unwrap(), &storage_config);
-        rocksdb_rollback(&db, backup_height, false, false);
-    } else {
-        panic!("storage config not found")
-    };
+    let storage_db = storage_db_at(&config_path, &storage_backup_path).await;
+    storage_rollback(&storage_db, backup_height, false).await;
     println!("backup done!");
 }
@@ -0,0 +1,136 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::rollback::{clean_consensus_data, clean_dir, controller_wal_path, LOCK_ID_CHAIN_ID};
+use crate::storage::{storage_db_at, storage_path, storage_rocksdb_dirs, Storage};
+use crate::util::{copy_dir, executor_db_path, parse_hex, read_config};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, PartialEq, Eq)]
+enum StorageBackend {
+    Rocksdb,
+    Opendal,
+}
+
+// storage_rocksdb db_path is a rocksdb itself
+fn backup_backend(storage_backup_path: &Path) -> StorageBackend {
+    if storage_rocksdb_dirs(storage_backup_path).contains(&storage_backup_path.to_path_buf()) {
+        StorageBackend::Rocksdb
+    } else {
+        StorageBackend::Opendal
+    }
+}
+
+fn config_backend(config_path: &Path) -> StorageBackend {
+    let config = read_config(config_path);
+    if config.contains_key("storage_rocksdb") {
+        StorageBackend::Rocksdb
+    } else if config.contains_key("storage_opendal") {
+        StorageBackend::Opendal
+    } else {
+        panic!("storage config not found")
+    }
+}
+
+// chain_id of system_config in config
+fn config_chain_id(config_path: &Path) -> Vec<u8> {
+    let chain_id = read_config(config_path)
+        .get("system_config")
+        .and_then(|system_config| system_config.get("chain_id"))
+        .and_then(|chain_id| chain_id.as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| panic!("system_config.chain_id not found in config"));
+    parse_hex(&chain_id)
+}
+
+pub async fn bootstrap(config_path: &Path, backup_path: &Path) {
+    // backup layout, the same as backup and export
+    let state_backup_path = backup_path.join("data/statedb");
+    let chain_backup_path = backup_path.join("data/nosql");
+    let storage_backup_path = backup_path.join("chain_data");
+    for path in [&state_backup_path, &chain_backup_path, &storage_backup_path] {
+        if !path.is_dir() {
+            panic!("{} not found in backup", path.display());
+        }
+    }
+
+    // data paths of the new node
+    let executor_db_path = executor_db_path(config_path);
+    let state_path = executor_db_path.clone() + "/statedb";
+    let chain_path = executor_db_path + "/nosql";
+    let storage_path = storage_path(config_path);
+    for path in [&state_path, &chain_path, &storage_path] {
+        if fs::read_dir(path).is_ok_and(|mut dir| dir.next().is_some()) {
+            panic!("{} is not empty, the node already has data", path);
+        }
+    }
+
+    // storage_db_at opens the backup with the backend of config, they must be the same
+    let backend = backup_backend(&storage_backup_path);
+    if backend != config_backend(config_path) {
+        panic!(
+            "backup storage is {:?} but the new node uses {:?}",
+            backend,
+            config_backend(config_path)
+        );
+    }
+
+    // check the backup belongs to the chain of config before any write
+    let storage_db = storage_db_at(config_path, &storage_backup_path).await;
+    let backup_height = storage_db.current_height().await;
+    // export of a range not from genesis can't start a node
+    if storage_db.block_hash(0).await.is_err() {
+        panic!("genesis block not found in backup, maybe a partial export");
+    }
+    let expected = config_chain_id(config_path);
+    match storage_db.load(0, &LOCK_ID_CHAIN_ID.to_be_bytes()).await {
+        Ok(chain_id) if chain_id == expected => {
+            println!("chain_id: 0x{}", hex::encode(&chain_id))
+        }
+        Ok(chain_id) => panic!(
+            "chain_id mismatch, backup: 0x{}, config: 0x{}",
+            hex::encode(&chain_id),
+            hex::encode(&expected)
+        ),
+        Err(_) => {
+            println!("chain_id not found in backup, maybe version lower than v6.3.2, skip check")
+        }
+    }
+    drop(storage_db);
+    println!("backup height: {}", backup_height);
+
+    // place data as the config of the new node
+    for (source, target) in [
+        (&state_backup_path, &state_path),
+        (&chain_backup_path, &chain_path),
+        (&storage_backup_path, &storage_path),
+    ] {
+        // copy_dir copies into target if it exists
+        let _ = fs::remove_dir(target);
+        if let Some(parent) = Path::new(target).parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        copy_dir(source, Path::new(target));
+        println!("copy {} to {} done!", source.display(), target);
+    }
+
+    // consensus and controller wal belong to the node which made the backup
+    let mut cleaned = clean_consensus_data(config_path, None);
+    cleaned.push(clean_dir(&controller_wal_path(config_path), None));
+    for record in cleaned {
+        println!("clean: {}", record);
+    }
+    println!("bootstrap done!");
+}
@@ -0,0 +1,212 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::storage::cloud_operator;
+use crate::util::get_real_key;
+use opendal::Operator;
+use std::path::Path;
+use tokio::task::JoinSet;
+
+#[derive(Default)]
+struct CopyReport {
+    copied: usize,
+    skipped: usize,
+    failed: Vec<String>,
+}
+
+// copy an object unless target already has the same content, return whether copied
+async fn copy_object(source: &Operator, target: &Operator, key: &str) -> Result<bool, String> {
+    let source_meta = source
+        .stat(key)
+        .await
+        .map_err(|e| format!("stat failed: {e}"))?;
+    // migrated by an earlier run, compare size and etag before reading the content
+    let same_size = match target.stat(key).await {
+        Ok(target_meta) if target_meta.content_length() == source_meta.content_length() => {
+            if source_meta.etag().is_some() && source_meta.etag() == target_meta.etag() {
+                return Ok(false);
+            }
+            true
+        }
+        _ => false,
+    };
+    let value = source
+        .read(key)
+        .await
+        .map_err(|e| format!("read failed: {e}"))?
+        .to_vec();
+    if same_size {
+        // etag differs between providers for the same content, so compare the content
+        if let Ok(existing) = target.read(key).await {
+            if existing.to_vec() == value {
+                return Ok(false);
+            }
+        }
+    }
+    target
+        .write(key, value.clone())
+        .await
+        .map_err(|e| format!("write failed: {e}"))?;
+    let written = target
+        .read(key)
+        .await
+        .map_err(|e| format!("read back failed: {e}"))?
+        .to_vec();
+    if written != value {
+        return Err("content mismatch after write".to_string());
+    }
+    Ok(true)
+}
+
+async fn copy_objects(
+    source: &Operator,
+    target: &Operator,
+    keys: Vec<String>,
+    jobs: usize,
+) -> CopyReport {
+    let total = keys.len();
+    let mut keys = keys.into_iter();
+    let mut tasks = JoinSet::new();
+    let mut report = CopyReport::default();
+    loop {
+        while tasks.len() < jobs.max(1) {
+            let Some(key) = keys.next() else {
+                break;
+            };
+            let source = source.clone();
+            let target = target.clone();
+            tasks.spawn(async move {
+                let result = copy_object(&source, &target, &key).await;
+                (key, result)
+            });
+        }
+        let Some(joined) = tasks.join_next().await else {
+            break;
+        };
+        match joined.unwrap() {
+            (_, Ok(true)) => report.copied += 1,
+            (_, Ok(false)) => report.skipped += 1,
+            (key, Err(e)) => report.failed.push(format!("{}: {}", key, e)),
+        }
+        print!(
+            "\rmigrating: {}/{}",
+            report.copied + report.skipped + report.failed.len(),
+            total
+        );
+    }
+    println!();
+    report
+}
+
+// every object except the backup progress and dirs like quarantine prefix
+// keys are collected before copying, about 100 bytes each, so a bucket of 10 million objects takes 1GB memory
+async fn object_keys(operator: &Operator, progress_key: &str) -> Vec<String> {
+    operator
+        .list("/")
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.path().to_string())
+        .filter(|key| !key.ends_with('/') && key != progress_key)
+        .collect()
+}
+
+pub async fn cloud_migrate(config_path: &Path, to_config_path: &Path, jobs: usize) {
+    // only cloud_storage of both configs is used, no local storage is opened
+    let source = cloud_operator(config_path);
+    let target = cloud_operator(to_config_path);
+
+    // backup progress is copied last, so target never claims blocks it doesn't have
+    let progress_key = get_real_key(0, &1u64.to_be_bytes());
+    let keys = object_keys(&source, &progress_key).await;
+    println!("{} cloud objects to migrate", keys.len());
+
+    let report = copy_objects(&source, &target, keys, jobs).await;
+    println!(
+        "copied {}, skipped {} already migrated, failed {}",
+        report.copied,
+        report.skipped,
+        report.failed.len()
+    );
+    if !report.failed.is_empty() {
+        for line in &report.failed {
+            println!("  {}", line);
+        }
+        panic!("cloud migrate incomplete, run it again to resume");
+    }
+
+    match source.read(&progress_key).await {
+        Ok(progress) => {
+            target
+                .write(&progress_key, progress.to_vec())
+                .await
+                .unwrap();
+            println!("backup progress migrated");
+        }
+        Err(_) => println!("backup hasn't started, no backup progress to migrate"),
+    }
+    println!("cloud migrate done!");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opendal::services::Fs;
+    use std::fs;
+
+    fn fs_operator(name: &str) -> Operator {
+        let root =
+            std::env::temp_dir().join(format!("cloud-op-migrate-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&root);
+        let mut builder = Fs::default();
+        builder.root(root.to_str().unwrap());
+        Operator::new(builder).unwrap().finish()
+    }
+
+    #[tokio::test]
+    async fn migrate_between_fs() {
+        let source = fs_operator("source");
+        let target = fs_operator("target");
+        let progress_key = get_real_key(0, &1u64.to_be_bytes());
+        for height in 0u64..8 {
+            source
+                .write(
+                    &get_real_key(4, &height.to_be_bytes()),
+                    vec![height as u8; 32],
+                )
+                .await
+                .unwrap();
+        }
+        source.write(&progress_key, vec![0u8; 12]).await.unwrap();
+
+        let keys = object_keys(&source, &progress_key).await;
+        assert_eq!(keys.len(), 8);
+        let report = copy_objects(&source, &target, keys.clone(), 3).await;
+        assert_eq!((report.copied, report.skipped), (8, 0));
+        assert!(report.failed.is_empty());
+
+        // resume skips what is already migrated
+        let report = copy_objects(&source, &target, keys.clone(), 3).await;
+        assert_eq!((report.copied, report.skipped), (0, 8));
+
+        // a corrupt object of the same size is copied again
+        target.write(&keys[0], vec![0xff; 32]).await.unwrap();
+        let report = copy_objects(&source, &target, keys.clone(), 3).await;
+        assert_eq!((report.copied, report.skipped), (1, 7));
+        assert_eq!(
+            target.read(&keys[0]).await.unwrap().to_vec(),
+            source.read(&keys[0]).await.unwrap().to_vec()
+        );
+    }
+}
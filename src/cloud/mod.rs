@@ -0,0 +1,48 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod migrate;
+mod restore;
+mod status;
+mod verify;
+
+use crate::util::{bytes_to_u64, get_real_key};
+pub use migrate::cloud_migrate;
+pub use restore::cloud_restore;
+pub use status::cloud_status;
+use storage_opendal::storager::Storager;
+pub use verify::{cloud_verify, parse_height_range};
+
+/// value of key(0, 1) in cloud storage include backup height(u64) and backup index(u32)
+pub async fn backup_progress(cloud: &Storager) -> Option<(u64, u32)> {
+    let progress = cloud
+        .operator
+        .read(&get_real_key(0, &1u64.to_be_bytes()))
+        .await
+        .ok()?
+        .to_vec();
+    if progress.len() < 8 {
+        println!(
+            "invalid cloud backup progress: 0x{}",
+            hex::encode(&progress)
+        );
+        return None;
+    }
+    // backup index may be absent, take it as 0
+    let index = progress
+        .get(8..12)
+        .map(|index| u32::from_be_bytes(index.try_into().unwrap()))
+        .unwrap_or(0);
+    Some((bytes_to_u64(&progress), index))
+}
@@ -0,0 +1,117 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::backup_progress;
+use crate::rollback::utxo_rebuild;
+use crate::storage::{cloud_storager, storage_db, storage_path, Storage, StorageDb};
+use crate::util::{executor_db_path, get_real_key};
+use std::fs;
+use std::path::Path;
+use storage_opendal::{config::StorageConfig as OpendalConfig, storager::Storager};
+
+pub async fn cloud_restore(config_path: &Path, height: Option<u64>) {
+    // restore into an empty data root, never mix with existing local data
+    let data_root = storage_path(config_path);
+    if fs::read_dir(&data_root).is_ok_and(|mut dir| dir.next().is_some()) {
+        panic!(
+            "local storage({}) is not empty, move it away before restore",
+            data_root
+        );
+    }
+    // restore into a scratch dir, the node storager below already opens data_root
+    let restore_root = format!("{}.restore", data_root.trim_end_matches('/'));
+    if Path::new(&restore_root).exists() {
+        panic!(
+            "{} exists, maybe left by a failed restore, remove it first",
+            restore_root
+        );
+    }
+
+    let storage_db = storage_db(config_path).await;
+    let StorageDb::Opendal(storager) = &storage_db else {
+        panic!("cloud restore not support rocksdb")
+    };
+    let cloud = cloud_storager(storager, config_path);
+
+    let Some((backup_height, _)) = backup_progress(cloud).await else {
+        panic!("backup hasn't started, nothing to restore")
+    };
+    // backup of backup_height maybe hasn't completed
+    let max_height = backup_height
+        .checked_sub(1)
+        .unwrap_or_else(|| panic!("no block has been backed up"));
+    let height = height.unwrap_or(max_height);
+    println!("cloud backup height: {}", backup_height);
+    println!("restore height: {}", height);
+    if height > max_height {
+        panic!(
+            "restore height({}) > last backed up height({})",
+            height, max_height
+        );
+    }
+
+    // a local only storager, so restoring never writes back to cloud
+    let config = OpendalConfig::default();
+    let local = Storager::build(
+        &restore_root,
+        &config.cloud_storage,
+        &config.exporter,
+        config.l1_capacity,
+        config.l2_capacity,
+    )
+    .await;
+    for h in 0..=height {
+        print!("\rrestoring: {}/{}", h, height);
+        let block_data = cloud
+            .load_block_data(h)
+            .await
+            .map_err(|e| println!("\nload block({h}) from cloud failed: {e}"))
+            .unwrap();
+        local
+            .store_all_block_data(&h.to_be_bytes(), &block_data)
+            .await
+            .unwrap();
+    }
+    println!();
+
+    // current hash is a virtual key of storage_opendal, only current height need to be set
+    Storager::store(
+        &local,
+        &get_real_key(0, &0u64.to_be_bytes()),
+        &height.to_be_bytes(),
+    )
+    .await
+    .unwrap();
+    println!("restore storage done!");
+
+    utxo_rebuild(&local, height).await;
+
+    // release both storagers before moving restored data into place
+    drop(local);
+    drop(storage_db);
+    // data_root was empty before restore, only holds what the node storager created
+    if Path::new(&data_root).exists() {
+        fs::remove_dir_all(&data_root).unwrap();
+    }
+    fs::rename(&restore_root, &data_root).unwrap();
+    println!("move {} to {} done!", restore_root, data_root);
+
+    println!("cloud restore done!");
+    println!(
+        "executor state is not restored, clear {} and let executor resync from genesis, \
+        or copy in a statedb snapshot of height {} (see backup/export)",
+        executor_db_path(config_path),
+        height
+    );
+}
@@ -0,0 +1,67 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::backup_progress;
+use crate::storage::{cloud_storager, storage_db, Storage, StorageDb};
+use crate::util::{bytes_to_u64, get_real_key};
+use std::path::Path;
+
+pub async fn cloud_status(config_path: &Path, samples: u64) {
+    let storage_db = storage_db(config_path).await;
+    let StorageDb::Opendal(storager) = &storage_db else {
+        panic!("cloud status not support rocksdb")
+    };
+    let cloud = cloud_storager(storager, config_path);
+
+    let current_height = storage_db.current_height().await;
+    println!("local current height: {}", current_height);
+    match storager
+        .load(&get_real_key(0, &2u64.to_be_bytes()), true)
+        .await
+    {
+        Ok(delete_height_bytes) => {
+            println!(
+                "local delete height: {}",
+                bytes_to_u64(&delete_height_bytes)
+            )
+        }
+        Err(_) => println!("local delete height: none"),
+    }
+
+    let Some((backup_height, backup_index)) = backup_progress(cloud).await else {
+        println!("backup hasn't started");
+        return;
+    };
+    println!("cloud backup height: {}", backup_height);
+    println!("cloud backup index: {}", backup_index);
+    let gap = current_height.saturating_sub(backup_height);
+    println!("gap to local current height: {}", gap);
+
+    // sample the window [backup_height, current_height] to see which blocks are already in cloud
+    if gap == 0 || samples == 0 {
+        return;
+    }
+    let step = (gap / samples).max(1);
+    println!("sample blocks in cloud:");
+    let mut height = backup_height;
+    while height <= current_height {
+        let exists = Storage::load_full_block(cloud, height).await.is_ok();
+        println!(
+            "  height {}: {}",
+            height,
+            if exists { "exists" } else { "missing" }
+        );
+        height += step;
+    }
+}
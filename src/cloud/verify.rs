@@ -0,0 +1,155 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::backup_progress;
+use crate::storage::{cloud_storager, local_storager, storage_db, Storage, StorageDb};
+use crate::util::get_real_key;
+use cita_cloud_proto::blockchain::Block;
+use prost::Message;
+use std::ops::Range;
+use std::path::Path;
+use storage_opendal::storager::Storager;
+
+/// parse height range like `100..200`, end excluded, either side can be omitted
+pub fn parse_height_range(s: &str) -> Result<(Option<u64>, Option<u64>), String> {
+    let (start, end) = s
+        .split_once("..")
+        .ok_or_else(|| format!("invalid range({s}), expect start..end"))?;
+    let parse = |h: &str| {
+        if h.is_empty() {
+            Ok(None)
+        } else {
+            h.parse::<u64>()
+                .map(Some)
+                .map_err(|e| format!("invalid height({h}): {e}"))
+        }
+    };
+    Ok((parse(start)?, parse(end)?))
+}
+
+// load key from the given layer only
+async fn load_layer(layer: &Storager, region: u32, height: u64) -> Option<Vec<u8>> {
+    layer
+        .load(&get_real_key(region, &height.to_be_bytes()), false)
+        .await
+        .ok()
+}
+
+pub async fn cloud_verify(config_path: &Path, range: Option<(Option<u64>, Option<u64>)>) {
+    let storage_db = storage_db(config_path).await;
+    let StorageDb::Opendal(storager) = &storage_db else {
+        panic!("cloud verify not support rocksdb")
+    };
+    let cloud = cloud_storager(storager, config_path);
+    let local = local_storager(storager, config_path);
+
+    let Some((backup_height, _)) = backup_progress(cloud).await else {
+        println!("backup hasn't started");
+        return;
+    };
+    // heights below backup height have been uploaded
+    let (start, end) = range.unwrap_or_default();
+    let heights: Range<u64> = start.unwrap_or(0)..end.unwrap_or(backup_height);
+    if heights.end > backup_height {
+        println!(
+            "warning: cloud backup height is {}, blocks from it are not uploaded yet",
+            backup_height
+        );
+    }
+    println!(
+        "verify cloud blocks in [{}, {})",
+        heights.start, heights.end
+    );
+
+    let mut missing = Vec::new();
+    let mut mismatched = Vec::new();
+    let mut prev_hash: Option<Vec<u8>> = None;
+    for height in heights.clone() {
+        // region 4: height -> block hash
+        let Some(remote_hash) = load_layer(cloud, 4, height).await else {
+            missing.push(format!("height {}: block hash missing", height));
+            prev_hash = None;
+            continue;
+        };
+        let remote_block_bytes = match Storage::load_full_block(cloud, height).await {
+            Ok(block_bytes) => block_bytes,
+            Err(e) => {
+                missing.push(format!("height {}: full block missing: {}", height, e));
+                prev_hash = Some(remote_hash);
+                continue;
+            }
+        };
+        let block = match Block::decode(remote_block_bytes.as_slice()) {
+            Ok(block) => block,
+            Err(e) => {
+                mismatched.push(format!("height {}: decode block failed: {}", height, e));
+                prev_hash = Some(remote_hash);
+                continue;
+            }
+        };
+        let header = block.header.unwrap_or_default();
+        if header.height != height {
+            mismatched.push(format!(
+                "height {}: block header height is {}",
+                height, header.height
+            ));
+        }
+        // hash chain continuity within the cloud data
+        if let Some(prev_hash) = &prev_hash {
+            if &header.prevhash != prev_hash {
+                mismatched.push(format!(
+                    "height {}: prevhash 0x{} != hash of height {} 0x{}",
+                    height,
+                    hex::encode(&header.prevhash),
+                    height - 1,
+                    hex::encode(prev_hash)
+                ));
+            }
+        }
+        // compare hash and full block with local storage if it still has the block
+        if let Some(local_hash) = load_layer(local, 4, height).await {
+            if local_hash != remote_hash {
+                mismatched.push(format!(
+                    "height {}: cloud hash 0x{} != local hash 0x{}",
+                    height,
+                    hex::encode(&remote_hash),
+                    hex::encode(&local_hash)
+                ));
+            }
+            match Storage::load_full_block(local, height).await {
+                Ok(local_block_bytes) if local_block_bytes != remote_block_bytes => mismatched
+                    .push(format!(
+                        "height {}: cloud full block differs from local",
+                        height
+                    )),
+                Ok(_) => {}
+                Err(e) => println!("height {}: load local full block failed: {}", height, e),
+            }
+        }
+        prev_hash = Some(remote_hash);
+    }
+
+    println!(
+        "verified {} blocks, {} missing, {} mismatched",
+        heights.end.saturating_sub(heights.start),
+        missing.len(),
+        mismatched.len()
+    );
+    for line in missing.iter().chain(mismatched.iter()) {
+        println!("  {}", line);
+    }
+    if missing.is_empty() && mismatched.is_empty() {
+        println!("cloud verify done!");
+    }
+}
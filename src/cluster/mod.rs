@@ -0,0 +1,172 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::guard::node_running;
+use crate::rollback::{cloud_storage_rollback, consistent_height, rollback, CloudPurge};
+use crate::storage::has_cloud_storage;
+use std::env::set_current_dir;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::Table;
+
+/// a node listed in nodes file
+pub struct Node {
+    pub root: PathBuf,
+    pub config_path: PathBuf,
+}
+
+/// nodes file is a toml with `[[node]]` tables of `root` and optional `config`(default config.toml)
+/// relative paths of root are based on the dir of nodes file, config is based on root
+pub fn read_nodes(nodes_path: &Path) -> Vec<Node> {
+    let content = fs::read_to_string(nodes_path)
+        .unwrap_or_else(|e| panic!("read {} failed: {}", nodes_path.display(), e));
+    let table: Table = content
+        .parse()
+        .unwrap_or_else(|e| panic!("parse {} failed: {}", nodes_path.display(), e));
+    let base = nodes_path.parent().unwrap_or(Path::new("."));
+    let nodes = table
+        .get("node")
+        .and_then(|nodes| nodes.as_array())
+        .unwrap_or_else(|| panic!("no [[node]] in {}", nodes_path.display()));
+    nodes
+        .iter()
+        .map(|node| {
+            let root = node
+                .get("root")
+                .and_then(|root| root.as_str())
+                .unwrap_or_else(|| panic!("node without root in {}", nodes_path.display()));
+            let root = base.join(root);
+            let config = node
+                .get("config")
+                .and_then(|config| config.as_str())
+                .unwrap_or("config.toml");
+            let config_path = root.join(config);
+            Node { root, config_path }
+        })
+        .collect()
+}
+
+// reasons the node can't be rolled back to height
+async fn pre_check(node: &Node, height: u64, force: bool) -> Vec<String> {
+    if set_current_dir(&node.root).is_err() {
+        return vec![format!("node root {} not found", node.root.display())];
+    }
+    if !node.config_path.is_file() {
+        return vec![format!("config {} not found", node.config_path.display())];
+    }
+    let mut reasons = Vec::new();
+    if !force {
+        reasons.extend(
+            node_running(&node.config_path)
+                .into_iter()
+                .map(|reason| format!("node is running: {}", reason)),
+        );
+    }
+    match consistent_height(&node.config_path).await {
+        Some(consistent_height) if consistent_height >= height => {}
+        Some(consistent_height) => reasons.push(format!(
+            "consistent height({}) < rollback height({})",
+            consistent_height, height
+        )),
+        None => reasons.push("no consistent height found".to_string()),
+    }
+    reasons
+}
+
+pub async fn cluster_rollback(nodes_path: &Path, height: u64, force: bool, archive: bool) {
+    let nodes = read_nodes(nodes_path);
+    println!("rollback {} nodes to height {}", nodes.len(), height);
+
+    // check every node before any write
+    let mut failed = false;
+    for node in nodes.iter() {
+        let reasons = pre_check(node, height, force).await;
+        if reasons.is_empty() {
+            println!("pre-check {}: ok", node.root.display());
+        } else {
+            failed = true;
+            for reason in reasons {
+                println!("pre-check {}: {}", node.root.display(), reason);
+            }
+        }
+    }
+    if failed {
+        panic!("pre-check failed, no node has been changed");
+    }
+
+    let mut report = Vec::new();
+    let mut aborted = false;
+    for node in nodes.iter() {
+        let name = node.root.display().to_string();
+        if aborted {
+            report.push((name, "not started".to_string()));
+            continue;
+        }
+        println!("rollback {}", name);
+        assert!(set_current_dir(&node.root).is_ok());
+        // run in a task so that a failed node is reported instead of aborting silently
+        let config_path = node.config_path.clone();
+        let result = tokio::spawn(async move {
+            rollback(&config_path, height, true, false, archive, false).await
+        })
+        .await;
+        match result {
+            Ok(true) => report.push((name, "rollback done".to_string())),
+            Ok(false) => report.push((name, "already at height, nothing changed".to_string())),
+            Err(e) => {
+                // stop here, the rest nodes and cloud storage keep the old chain
+                report.push((name, format!("rollback failed: {}", e)));
+                aborted = true;
+            }
+        }
+    }
+
+    // cloud storage is shared by the chain, rollback it only once after all nodes succeed
+    let cloud_node = nodes
+        .iter()
+        .find(|node| has_cloud_storage(&node.config_path));
+    match cloud_node {
+        _ if aborted => report.push((
+            "cloud storage".to_string(),
+            "skipped, a node rollback failed".to_string(),
+        )),
+        Some(node) => {
+            assert!(set_current_dir(&node.root).is_ok());
+            let config_path = node.config_path.clone();
+            let result = tokio::spawn(async move {
+                cloud_storage_rollback(&config_path, height, CloudPurge::Keep, false, false).await
+            })
+            .await;
+            report.push((
+                "cloud storage".to_string(),
+                match result {
+                    Ok(()) => format!("rollback done with config of {}", node.root.display()),
+                    Err(e) => format!("rollback failed: {}", e),
+                },
+            ));
+        }
+        None => report.push((
+            "cloud storage".to_string(),
+            "not configured, skip".to_string(),
+        )),
+    }
+
+    println!("cluster rollback report:");
+    for (name, result) in report {
+        println!("  {}: {}", name, result);
+    }
+    if aborted {
+        panic!("cluster rollback aborted, fix the failed node and run it again");
+    }
+}
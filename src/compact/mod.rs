@@ -0,0 +1,104 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::storage::{storage_path, storage_rocksdb_dirs};
+use crate::util::executor_db_path;
+use clap::ValueEnum;
+use rocksdb::{Cache, Env, Options, DB};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CompactDb {
+    /// storage_rocksdb db, or rocksdb under data_root of storage_opendal
+    Storage,
+    /// executor state db
+    Statedb,
+    /// executor chain db
+    Nosql,
+}
+
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+// full range compaction of every column family
+// open with the options persisted by the owner of the db, so sst files keep their compression and table settings
+fn compact_db(path: &Path) {
+    let (options, cf_descriptors) =
+        Options::load_latest(path, Env::new().unwrap(), true, Cache::new_lru_cache(0))
+            .map_err(|e| println!("load options of {} failed: {}", path.display(), e))
+            .unwrap();
+    let cfs: Vec<String> = cf_descriptors
+        .iter()
+        .map(|cf| cf.name().to_string())
+        .collect();
+    let db = DB::open_cf_descriptors(&options, path, cf_descriptors)
+        .map_err(|e| println!("open {} failed: {}", path.display(), e))
+        .unwrap();
+    for cf in cfs.iter() {
+        let handle = db.cf_handle(cf).unwrap();
+        db.compact_range_cf(handle, None::<&[u8]>, None::<&[u8]>);
+    }
+}
+
+pub fn compact(config_path: &Path, dbs: &[CompactDb]) {
+    let selected = |db: CompactDb| dbs.is_empty() || dbs.contains(&db);
+    let executor_db_path = executor_db_path(config_path);
+
+    let mut paths = Vec::new();
+    if selected(CompactDb::Storage) {
+        paths.extend(storage_rocksdb_dirs(Path::new(&storage_path(config_path))));
+    }
+    if selected(CompactDb::Statedb) {
+        paths.push(PathBuf::from(executor_db_path.clone() + "/statedb"));
+    }
+    if selected(CompactDb::Nosql) {
+        paths.push(PathBuf::from(executor_db_path + "/nosql"));
+    }
+
+    let mut report = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            println!("{} not exist, skip", path.display());
+            continue;
+        }
+        println!("compacting {}", path.display());
+        let before = dir_size(&path);
+        compact_db(&path);
+        let after = dir_size(&path);
+        report.push((path, before, after));
+    }
+
+    println!("compact report:");
+    for (path, before, after) in report {
+        println!(
+            "  {}: {} -> {} bytes, freed {} bytes",
+            path.display(),
+            before,
+            after,
+            before.saturating_sub(after)
+        );
+    }
+}
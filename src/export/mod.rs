@@ -14,9 +14,8 @@
 
 mod state_backup;
 use self::state_backup::state_snapshot_backup;
-use crate::util::{
-    copy_dir, executor_db_path, get_real_key, read_current_height, storage_db, StorageDb,
-};
+use crate::storage::{storage_db, Storage};
+use crate::util::{copy_dir, executor_db_path, HASH_LEN};
 use cita_cloud_proto::blockchain::raw_transaction::Tx::UtxoTx;
 use cita_cloud_proto::blockchain::Block;
 use cita_cloud_proto::storage::Regions;
@@ -35,7 +34,7 @@ pub async fn export(
     let storage_db = storage_db(&config_path).await;
     let executor_db_path = executor_db_path(&config_path);
 
-    let current_height = read_current_height(&storage_db).await;
+    let current_height = storage_db.current_height().await;
     println!("current height: {}", current_height);
 
     if end_height > current_height || begin_height > end_height {
@@ -59,98 +58,47 @@ pub async fn export(
     println!("copy excutor chain_db done!");
 
     // export storage data
-    // convert storage rocksdb data to storage opendal data
-    if let StorageDb::RocksDB(read) = &storage_db {
-        let storage_export_path = export_path.clone().join("chain_data");
-        let config = OpendalConfig::default();
-        let write = Storager::build(
-            storage_export_path.to_str().unwrap(),
-            &config.cloud_storage,
-            &config.exporter,
-            config.l1_capacity,
-            config.l2_capacity,
-        )
-        .await;
-        for height in begin_height..=end_height {
-            print!("\rconverting old: {}", height);
-            let height_bytes = height.to_be_bytes().to_vec();
-            let old_block_bytes = read.load_full_block(height_bytes.clone()).unwrap();
-            let mut old_bytes = read.load(4, height_bytes.clone()).unwrap();
-            old_bytes.extend_from_slice(&old_block_bytes);
-
-            write
-                .store_all_block_data(&height_bytes, &old_bytes)
-                .await
-                .unwrap();
-
-            // handle utxo tx
-            let block = Block::decode(old_block_bytes.as_slice()).unwrap();
-            let global_region = i32::from(Regions::Global) as u32;
-            for raw_tx in block.body.unwrap().body {
-                if let UtxoTx(utxo_tx) = raw_tx.tx.unwrap() {
-                    let tx_hash = utxo_tx.transaction_hash;
-                    let lock_id = utxo_tx.transaction.unwrap().lock_id;
-
-                    write
-                        .store(
-                            &get_real_key(global_region, lock_id.to_be_bytes().as_ref()),
-                            tx_hash.as_slice(),
-                        )
-                        .await
-                        .unwrap();
-                }
-            }
-        }
-        println!("\nexport block done!");
-    } else if let StorageDb::Opendal(read) = &storage_db {
-        // export storage opendal data to storage opendal data
-        let storage_export_path = export_path.clone().join("chain_data");
-        let config = OpendalConfig::default();
-        let write = Storager::build(
-            storage_export_path.to_str().unwrap(),
-            &config.cloud_storage,
-            &config.exporter,
-            config.l1_capacity,
-            config.l2_capacity,
-        )
-        .await;
-        for height in begin_height..=end_height {
-            print!("\rexporting: {}", height);
-            let height_bytes = height.to_be_bytes().to_vec();
-            let block_bytes = read.load_full_block(&height_bytes).await.unwrap();
-            let mut block_hash_bytes = read
-                .load(&get_real_key(4, &height_bytes), true)
-                .await
-                .unwrap();
-            block_hash_bytes.extend_from_slice(&block_bytes);
-
-            write
-                .store_all_block_data(&height_bytes, &block_hash_bytes)
+    // convert storage rocksdb or opendal data to storage opendal data
+    let storage_export_path = export_path.clone().join("chain_data");
+    let config = OpendalConfig::default();
+    let write = Storager::build(
+        storage_export_path.to_str().unwrap(),
+        &config.cloud_storage,
+        &config.exporter,
+        config.l1_capacity,
+        config.l2_capacity,
+    )
+    .await;
+    for height in begin_height..=end_height {
+        print!("\rexporting: {}", height);
+        let height_bytes = height.to_be_bytes().to_vec();
+        let block_data = storage_db.load_block_data(height).await.unwrap();
+
+        write
+            .store_all_block_data(&height_bytes, &block_data)
+            .await
+            .unwrap();
+
+        // handle utxo tx
+        let block = Block::decode(&block_data[HASH_LEN as usize..]).unwrap();
+        let global_region = i32::from(Regions::Global) as u32;
+        for raw_tx in block.body.unwrap().body {
+            if let UtxoTx(utxo_tx) = raw_tx.tx.unwrap() {
+                let tx_hash = utxo_tx.transaction_hash;
+                let lock_id = utxo_tx.transaction.unwrap().lock_id;
+
+                Storage::store(
+                    &write,
+                    global_region,
+                    lock_id.to_be_bytes().as_ref(),
+                    tx_hash.as_slice(),
+                )
                 .await
                 .unwrap();
-
-            // handle utxo tx
-            let block = Block::decode(block_bytes.as_slice()).unwrap();
-            let global_region = i32::from(Regions::Global) as u32;
-            for raw_tx in block.body.unwrap().body {
-                if let UtxoTx(utxo_tx) = raw_tx.tx.unwrap() {
-                    let tx_hash = utxo_tx.transaction_hash;
-                    let lock_id = utxo_tx.transaction.unwrap().lock_id;
-
-                    write
-                        .store(
-                            &get_real_key(global_region, lock_id.to_be_bytes().as_ref()),
-                            tx_hash.as_slice(),
-                        )
-                        .await
-                        .unwrap();
-                }
             }
         }
-        println!("\nexport block done!");
-    } else {
-        panic!("storage db not found")
     }
+    println!("\nexport block done!");
 
     println!("export done!");
 }
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::util::state_block_header;
 use cita_database::{Config, DataCategory, Database, RocksDB, NUM_COLUMNS};
 use cita_trie::{PatriciaTrie, Trie, DB};
 use cita_types::{Address, H256};
@@ -38,17 +39,7 @@ pub fn state_snapshot_backup(state_path: &str, backup_path: &PathBuf, height: u6
     let backup_rocks_db = Arc::new(RocksDB::open(backup_path.to_str().unwrap(), &config).unwrap());
 
     // get block hash
-    let height_key = BlockNumber2Hash(height).get_index();
-    let block_hash = state_rocks_db
-        .get(Some(DataCategory::Extra), &height_key.to_vec())
-        .unwrap_or(None)
-        .map(|h| decode::<H256>(&h).unwrap())
-        .unwrap();
-    let block_header = state_rocks_db
-        .get(Some(DataCategory::Headers), block_hash.as_bytes())
-        .unwrap_or(None)
-        .map(|header| decode::<Header>(header.as_slice()).unwrap())
-        .unwrap();
+    let (block_hash, block_header) = state_block_header(&state_rocks_db, Some(height));
     let state_root = block_header.state_root();
 
     let block_hash_value = rlp::encode(&block_hash).to_vec();
@@ -0,0 +1,132 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::storage::{storage_path, storage_rocksdb_dirs};
+use crate::util::{executor_db_path, read_config};
+use std::fs;
+use std::net::{SocketAddr, TcpStream};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(200);
+
+// LOCK files of rocksdb under the data dirs of storage and executor
+fn lock_files(config_path: &Path) -> Vec<PathBuf> {
+    let executor_db_path = executor_db_path(config_path);
+    let mut dirs = storage_rocksdb_dirs(Path::new(&storage_path(config_path)));
+    dirs.push(PathBuf::from(executor_db_path.clone() + "/statedb"));
+    dirs.push(PathBuf::from(executor_db_path + "/nosql"));
+    dirs.into_iter()
+        .map(|dir| dir.join("LOCK"))
+        .filter(|lock| lock.is_file())
+        .collect()
+}
+
+// inode -> pid of posix locks held by other processes, see proc(5)
+fn posix_locks() -> Vec<(u64, u32)> {
+    let Ok(locks) = fs::read_to_string("/proc/locks") else {
+        return Vec::new();
+    };
+    locks
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().filter(|f| *f != "->").collect();
+            let pid = fields.get(4)?.parse::<u32>().ok()?;
+            let inode = fields.get(5)?.rsplit(':').next()?.parse::<u64>().ok()?;
+            (pid != std::process::id()).then_some((inode, pid))
+        })
+        .collect()
+}
+
+// every `*_port` in microservice sections of config
+fn service_ports(config_path: &Path) -> Vec<(String, u16)> {
+    read_config(config_path)
+        .iter()
+        .filter_map(|(section, value)| value.as_table().map(|table| (section, table)))
+        .flat_map(|(section, table)| {
+            table.iter().filter_map(move |(key, value)| {
+                let port = u16::try_from(value.as_integer()?).ok()?;
+                key.ends_with("_port")
+                    .then(|| (format!("{}.{}", section, key), port))
+            })
+        })
+        .collect()
+}
+
+// pid files in node root whose process is still alive
+fn alive_pid_files() -> Vec<(PathBuf, u32)> {
+    let Ok(entries) = fs::read_dir(".") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "pid"))
+        .filter_map(|path| {
+            let pid = fs::read_to_string(&path).ok()?.trim().parse::<u32>().ok()?;
+            Path::new(&format!("/proc/{}", pid))
+                .exists()
+                .then_some((path, pid))
+        })
+        .collect()
+}
+
+/// reasons to believe the node is still running
+pub fn node_running(config_path: &Path) -> Vec<String> {
+    let mut reasons = Vec::new();
+
+    let locks = posix_locks();
+    for lock in lock_files(config_path) {
+        let Ok(metadata) = fs::metadata(&lock) else {
+            continue;
+        };
+        if let Some((_, pid)) = locks.iter().find(|(inode, _)| *inode == metadata.ino()) {
+            reasons.push(format!("{} is held by pid {}", lock.display(), pid));
+        }
+    }
+
+    for (name, port) in service_ports(config_path) {
+        let addr = SocketAddr::from(([127, 0, 0, 1], port));
+        if TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).is_ok() {
+            reasons.push(format!("{} {} is listening", name, port));
+        }
+    }
+
+    for (pid_file, pid) in alive_pid_files() {
+        reasons.push(format!(
+            "{} points to running pid {}",
+            pid_file.display(),
+            pid
+        ));
+    }
+
+    reasons
+}
+
+/// abort if the node is still running, unless force
+pub fn ensure_node_stopped(config_path: &Path, force: bool) {
+    let reasons = node_running(config_path);
+    if reasons.is_empty() {
+        return;
+    }
+    for reason in reasons.iter() {
+        println!("node is running: {}", reason);
+    }
+    if force {
+        println!("--force is set, operate anyway");
+        return;
+    }
+    panic!("refuse to operate a running node, stop the node first or use --force");
+}
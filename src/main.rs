@@ -13,14 +13,33 @@
 // limitations under the License.
 
 mod backup;
+mod bootstrap;
+mod cloud;
+mod cluster;
+mod compact;
 mod export;
+mod guard;
+mod query;
 mod rollback;
+mod storage;
 mod util;
 
 use crate::backup::backup;
+use crate::bootstrap::bootstrap;
+use crate::cloud::{cloud_migrate, cloud_restore, cloud_status, cloud_verify, parse_height_range};
+use crate::cluster::cluster_rollback;
+use crate::compact::{compact, CompactDb};
 use crate::export::export;
-use crate::rollback::{cloud_storage_rollback, rollback};
-use clap::{Parser, Subcommand};
+use crate::guard::ensure_node_stopped;
+use crate::query::{
+    account, block, state_diff, storage_dump, sysconfig, sysconfig_history, tx, DumpFormat,
+};
+use crate::rollback::{
+    cloud_storage_rollback, consistent_height, diverge, export_hash_list, height_before_time,
+    height_before_tx, height_of_block, rebuild_utxo, rollback, CloudPurge,
+};
+use crate::util::confirm;
+use clap::{ArgGroup, Parser, Subcommand};
 use std::env::{current_dir, set_current_dir};
 use std::path::PathBuf;
 
@@ -36,6 +55,11 @@ struct Cli {
 enum Commands {
     /// rollback chain status to specified height
     #[clap(arg_required_else_help = true)]
+    #[clap(group(
+        ArgGroup::new("target")
+            .required(true)
+            .args(["height", "auto", "to_hash", "before_tx", "before_time"]),
+    ))]
     Rollback {
         /// chain config path
         #[clap(short, long, default_value = "config.toml")]
@@ -43,12 +67,38 @@ enum Commands {
         /// node root path
         #[clap(short, long, default_value = ".")]
         node_root: PathBuf,
+        /// operate even if the node seems running
+        #[clap(long)]
+        force: bool,
         /// the specified height that you want to rollback to
-        #[clap(required = true)]
-        height: u64,
+        height: Option<u64>,
         /// whether to clean consensus data
         #[clap(long = "clean")]
         clean_consensus_data: bool,
+        /// archive cleaned data into a timestamped dir under ./archive instead of deleting
+        #[clap(long)]
+        archive: bool,
+        /// keep controller wal instead of cleaning it
+        #[clap(long)]
+        keep_controller_wal: bool,
+        /// rollback to the last height where storage and executor are consistent
+        #[clap(long)]
+        auto: bool,
+        /// skip confirmation of auto rollback
+        #[clap(short, long, requires = "auto")]
+        yes: bool,
+        /// rollback to the block with this 0x prefixed hash
+        #[clap(long)]
+        to_hash: Option<String>,
+        /// rollback to the height just before the block containing this 0x prefixed tx hash
+        #[clap(long)]
+        before_tx: Option<String>,
+        /// rollback to the last block before this RFC3339 time, e.g. 2024-01-01T08:00:00+08:00
+        #[clap(long)]
+        before_time: Option<String>,
+        /// rebuild utxo lock_id state from blocks instead of walking the tx index
+        #[clap(long)]
+        rebuild_utxo: bool,
     },
     /// rollback cloud storage status to specified height
     #[clap(arg_required_else_help = true)]
@@ -59,6 +109,21 @@ enum Commands {
         /// node root path
         #[clap(short, long, default_value = ".")]
         node_root: PathBuf,
+        /// operate even if the node seems running
+        #[clap(long)]
+        force: bool,
+        /// delete cloud objects of blocks above the height
+        #[clap(long, conflicts_with = "quarantine")]
+        purge: bool,
+        /// move cloud objects of blocks above the height under the prefix instead of deleting them
+        #[clap(long)]
+        quarantine: Option<String>,
+        /// only list what would be changed
+        #[clap(long)]
+        dry_run: bool,
+        /// skip confirmation of purge or quarantine
+        #[clap(short, long)]
+        yes: bool,
         /// the specified height that you want to rollback to
         #[clap(required = true)]
         height: u64,
@@ -71,6 +136,9 @@ enum Commands {
         /// node root path
         #[clap(short, long, default_value = ".")]
         node_root: PathBuf,
+        /// operate even if the node seems running
+        #[clap(long)]
+        force: bool,
         /// backup path dir
         #[clap(short, long, default_value = "backup")]
         path: PathBuf,
@@ -86,6 +154,9 @@ enum Commands {
         /// node root path
         #[clap(short, long, default_value = ".")]
         node_root: PathBuf,
+        /// operate even if the node seems running
+        #[clap(long)]
+        force: bool,
         /// export path dir
         #[clap(short, long, default_value = "export")]
         path: PathBuf,
@@ -96,6 +167,288 @@ enum Commands {
         #[clap(short, long)]
         end_height: u64,
     },
+    /// show a block of specified height or hash
+    #[clap(arg_required_else_help = true)]
+    Block {
+        /// chain config path
+        #[clap(short, long, default_value = "config.toml")]
+        config_path: PathBuf,
+        /// node root path
+        #[clap(short, long, default_value = ".")]
+        node_root: PathBuf,
+        /// block height or 0x prefixed block hash
+        #[clap(required = true)]
+        block: String,
+        /// print raw protobuf bytes of the block in hex
+        #[clap(long, conflicts_with = "json")]
+        raw: bool,
+        /// print the full block in json
+        #[clap(long)]
+        json: bool,
+    },
+    /// show a transaction and where it landed
+    #[clap(arg_required_else_help = true)]
+    Tx {
+        /// chain config path
+        #[clap(short, long, default_value = "config.toml")]
+        config_path: PathBuf,
+        /// node root path
+        #[clap(short, long, default_value = ".")]
+        node_root: PathBuf,
+        /// 0x prefixed transaction hash
+        #[clap(required = true)]
+        hash: String,
+        /// print the transaction in json
+        #[clap(long)]
+        json: bool,
+    },
+    /// show an account state of executor at specified height
+    #[clap(arg_required_else_help = true)]
+    Account {
+        /// chain config path
+        #[clap(short, long, default_value = "config.toml")]
+        config_path: PathBuf,
+        /// node root path
+        #[clap(short, long, default_value = ".")]
+        node_root: PathBuf,
+        /// 0x prefixed account address
+        #[clap(required = true)]
+        address: String,
+        /// the specified height, default is current height of executor
+        #[clap(long)]
+        height: Option<u64>,
+        /// also print the contract code
+        #[clap(long)]
+        code: bool,
+        /// also print the contract abi
+        #[clap(long)]
+        abi: bool,
+        /// print the account in json
+        #[clap(long)]
+        json: bool,
+    },
+    /// dump storage slots of a contract at specified height
+    #[clap(arg_required_else_help = true)]
+    StorageDump {
+        /// chain config path
+        #[clap(short, long, default_value = "config.toml")]
+        config_path: PathBuf,
+        /// node root path
+        #[clap(short, long, default_value = ".")]
+        node_root: PathBuf,
+        /// 0x prefixed contract address
+        #[clap(required = true)]
+        address: String,
+        /// the specified height, default is current height of executor
+        #[clap(long)]
+        height: Option<u64>,
+        /// only dump slots whose key starts with this 0x prefixed hex
+        #[clap(long)]
+        prefix: Option<String>,
+        /// output format
+        #[clap(long, value_enum, default_value = "json")]
+        format: DumpFormat,
+    },
+    /// show executor state changes between two heights
+    #[clap(arg_required_else_help = true)]
+    StateDiff {
+        /// chain config path
+        #[clap(short, long, default_value = "config.toml")]
+        config_path: PathBuf,
+        /// node root path
+        #[clap(short, long, default_value = ".")]
+        node_root: PathBuf,
+        /// the height to diff from
+        #[clap(required = true)]
+        from_height: u64,
+        /// the height to diff to
+        #[clap(required = true)]
+        to_height: u64,
+        /// print the diff in json
+        #[clap(long)]
+        json: bool,
+    },
+    /// find the first height where block hashes differ from another node
+    Diverge {
+        /// chain config path
+        #[clap(short, long, default_value = "config.toml")]
+        config_path: PathBuf,
+        /// node root path
+        #[clap(short, long, default_value = ".")]
+        node_root: PathBuf,
+        /// chain config path of the other node
+        #[clap(long, requires = "other_root", required_unless_present_any = ["hash_list", "export"])]
+        other: Option<PathBuf>,
+        /// node root path of the other node
+        #[clap(long, requires = "other")]
+        other_root: Option<PathBuf>,
+        /// compare with an exported hash list instead of the other node
+        #[clap(long, conflicts_with_all = ["other", "export"])]
+        hash_list: Option<PathBuf>,
+        /// export block hashes of this node as a hash list instead of comparing
+        #[clap(long, conflicts_with = "other")]
+        export: Option<PathBuf>,
+    },
+    /// show the change history of system config
+    SysconfigHistory {
+        /// chain config path
+        #[clap(short, long, default_value = "config.toml")]
+        config_path: PathBuf,
+        /// node root path
+        #[clap(short, long, default_value = ".")]
+        node_root: PathBuf,
+    },
+    /// show the effective system config at specified height
+    Sysconfig {
+        /// chain config path
+        #[clap(short, long, default_value = "config.toml")]
+        config_path: PathBuf,
+        /// node root path
+        #[clap(short, long, default_value = ".")]
+        node_root: PathBuf,
+        /// the specified height, default is current height
+        #[clap(long)]
+        height: Option<u64>,
+    },
+    /// operate utxo lock_id state of system config
+    Utxo {
+        #[clap(subcommand)]
+        command: UtxoCommands,
+    },
+    /// operate cloud storage of storage_opendal
+    Cloud {
+        #[clap(subcommand)]
+        command: CloudCommands,
+    },
+    /// place data of a backup for a new node
+    Bootstrap {
+        /// chain config path of the new node
+        #[clap(short, long, default_value = "config.toml")]
+        config_path: PathBuf,
+        /// node root path of the new node
+        #[clap(short, long, default_value = ".")]
+        node_root: PathBuf,
+        /// operate even if the node seems running
+        #[clap(long)]
+        force: bool,
+        /// backup dir of a height, made by backup or export
+        #[clap(long)]
+        from_backup: PathBuf,
+    },
+    /// compact rocksdb of storage and executor
+    Compact {
+        /// chain config path
+        #[clap(short, long, default_value = "config.toml")]
+        config_path: PathBuf,
+        /// node root path
+        #[clap(short, long, default_value = ".")]
+        node_root: PathBuf,
+        /// operate even if the node seems running
+        #[clap(long)]
+        force: bool,
+        /// which db to compact, can be repeated, default all
+        #[clap(long, value_enum)]
+        db: Vec<CompactDb>,
+    },
+    /// operate all nodes of a chain
+    Cluster {
+        #[clap(subcommand)]
+        command: ClusterCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum ClusterCommands {
+    /// rollback all nodes and cloud storage to specified height
+    #[clap(arg_required_else_help = true)]
+    Rollback {
+        /// nodes file with [[node]] tables of root and config
+        #[clap(long)]
+        nodes: PathBuf,
+        /// operate even if nodes seem running
+        #[clap(long)]
+        force: bool,
+        /// archive cleaned data into a timestamped dir under ./archive instead of deleting
+        #[clap(long)]
+        archive: bool,
+        /// the specified height that you want to rollback to
+        #[clap(required = true)]
+        height: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum CloudCommands {
+    /// show backup progress of cloud storage
+    Status {
+        /// chain config path
+        #[clap(short, long, default_value = "config.toml")]
+        config_path: PathBuf,
+        /// node root path
+        #[clap(short, long, default_value = ".")]
+        node_root: PathBuf,
+        /// how many blocks to sample between cloud backup height and local current height
+        #[clap(long, default_value = "10")]
+        samples: u64,
+    },
+    /// verify blocks in cloud storage against local storage and the hash chain
+    Verify {
+        /// chain config path
+        #[clap(short, long, default_value = "config.toml")]
+        config_path: PathBuf,
+        /// node root path
+        #[clap(short, long, default_value = ".")]
+        node_root: PathBuf,
+        /// height range like start..end, end excluded, default all backed up blocks
+        #[clap(long, value_parser = parse_height_range)]
+        range: Option<(Option<u64>, Option<u64>)>,
+    },
+    /// restore local storage from cloud storage
+    Restore {
+        /// chain config path
+        #[clap(short, long, default_value = "config.toml")]
+        config_path: PathBuf,
+        /// node root path
+        #[clap(short, long, default_value = ".")]
+        node_root: PathBuf,
+        /// operate even if the node seems running
+        #[clap(long)]
+        force: bool,
+        /// the height to restore to, default the last backed up height
+        #[clap(long)]
+        height: Option<u64>,
+    },
+    /// copy all objects of cloud storage to the cloud storage of another config
+    Migrate {
+        /// chain config path
+        #[clap(short, long, default_value = "config.toml")]
+        config_path: PathBuf,
+        /// node root path
+        #[clap(short, long, default_value = ".")]
+        node_root: PathBuf,
+        /// config with the target storage_opendal cloud_storage
+        #[clap(long)]
+        to: PathBuf,
+        /// how many objects to copy concurrently
+        #[clap(long, default_value = "16")]
+        jobs: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum UtxoCommands {
+    /// rebuild utxo lock_id state from blocks, for a damaged tx index
+    Rebuild {
+        /// chain config path
+        #[clap(short, long, default_value = "config.toml")]
+        config_path: PathBuf,
+        /// node root path
+        #[clap(short, long, default_value = ".")]
+        node_root: PathBuf,
+        /// operate even if the node seems running
+        #[clap(long)]
+        force: bool,
+    },
 }
 #[tokio::main]
 async fn main() {
@@ -108,31 +461,81 @@ async fn operate(command: Commands) {
         Commands::Rollback {
             mut config_path,
             node_root,
+            force,
             height,
             clean_consensus_data,
+            archive,
+            keep_controller_wal,
+            auto,
+            yes,
+            to_hash,
+            before_tx,
+            before_time,
+            rebuild_utxo: rebuild_utxo_state,
         } => {
             if !config_path.is_absolute() {
                 config_path = current_dir().unwrap().join(config_path);
             }
             assert!(set_current_dir(&node_root).is_ok());
+            ensure_node_stopped(&config_path, force);
 
-            rollback(&config_path, height, clean_consensus_data).await;
+            let height = if auto {
+                let Some(height) = consistent_height(&config_path).await else {
+                    println!("no consistent height found, ignore rollback");
+                    return;
+                };
+                println!("last consistent height: {}", height);
+                if !yes && !confirm(&format!("rollback to height {}?", height)) {
+                    println!("rollback canceled");
+                    return;
+                }
+                height
+            } else if let Some(to_hash) = to_hash {
+                height_of_block(&config_path, &to_hash).await
+            } else if let Some(before_tx) = before_tx {
+                height_before_tx(&config_path, &before_tx).await
+            } else if let Some(before_time) = before_time {
+                height_before_time(&config_path, &before_time).await
+            } else {
+                height.unwrap()
+            };
+            rollback(
+                &config_path,
+                height,
+                clean_consensus_data,
+                rebuild_utxo_state,
+                archive,
+                keep_controller_wal,
+            )
+            .await;
         }
         Commands::CloudRollback {
             mut config_path,
             node_root,
+            force,
+            purge,
+            quarantine,
+            dry_run,
+            yes,
             height,
         } => {
             if !config_path.is_absolute() {
                 config_path = current_dir().unwrap().join(config_path);
             }
             assert!(set_current_dir(&node_root).is_ok());
+            ensure_node_stopped(&config_path, force);
 
-            cloud_storage_rollback(&config_path, height).await;
+            let purge = match quarantine {
+                Some(prefix) => CloudPurge::Quarantine(prefix),
+                None if purge => CloudPurge::Delete,
+                None => CloudPurge::Keep,
+            };
+            cloud_storage_rollback(&config_path, height, purge, dry_run, yes).await;
         }
         Commands::Backup {
             mut config_path,
             node_root,
+            force,
             path,
             height,
         } => {
@@ -140,6 +543,7 @@ async fn operate(command: Commands) {
                 config_path = current_dir().unwrap().join(config_path);
             }
             assert!(set_current_dir(node_root).is_ok());
+            ensure_node_stopped(&config_path, force);
             let mut backup_path = path;
             if !backup_path.is_absolute() {
                 backup_path = current_dir().unwrap().join(backup_path);
@@ -150,6 +554,7 @@ async fn operate(command: Commands) {
         Commands::Export {
             mut config_path,
             node_root,
+            force,
             path,
             begin_height,
             end_height,
@@ -158,6 +563,7 @@ async fn operate(command: Commands) {
                 config_path = current_dir().unwrap().join(config_path);
             }
             assert!(set_current_dir(node_root).is_ok());
+            ensure_node_stopped(&config_path, force);
             let mut export_path = path;
             if !export_path.is_absolute() {
                 export_path = current_dir().unwrap().join(export_path);
@@ -165,5 +571,257 @@ async fn operate(command: Commands) {
 
             export(config_path, export_path, begin_height, end_height).await;
         }
+        Commands::Block {
+            mut config_path,
+            node_root,
+            block: block_id,
+            raw,
+            json,
+        } => {
+            if !config_path.is_absolute() {
+                config_path = current_dir().unwrap().join(config_path);
+            }
+            assert!(set_current_dir(&node_root).is_ok());
+
+            block(&config_path, &block_id, raw, json).await;
+        }
+        Commands::Tx {
+            mut config_path,
+            node_root,
+            hash,
+            json,
+        } => {
+            if !config_path.is_absolute() {
+                config_path = current_dir().unwrap().join(config_path);
+            }
+            assert!(set_current_dir(&node_root).is_ok());
+
+            tx(&config_path, &hash, json).await;
+        }
+        Commands::Account {
+            mut config_path,
+            node_root,
+            address,
+            height,
+            code,
+            abi,
+            json,
+        } => {
+            if !config_path.is_absolute() {
+                config_path = current_dir().unwrap().join(config_path);
+            }
+            assert!(set_current_dir(&node_root).is_ok());
+
+            account(&config_path, &address, height, code, abi, json);
+        }
+        Commands::StorageDump {
+            mut config_path,
+            node_root,
+            address,
+            height,
+            prefix,
+            format,
+        } => {
+            if !config_path.is_absolute() {
+                config_path = current_dir().unwrap().join(config_path);
+            }
+            assert!(set_current_dir(&node_root).is_ok());
+
+            storage_dump(&config_path, &address, height, prefix, format);
+        }
+        Commands::StateDiff {
+            mut config_path,
+            node_root,
+            from_height,
+            to_height,
+            json,
+        } => {
+            if !config_path.is_absolute() {
+                config_path = current_dir().unwrap().join(config_path);
+            }
+            assert!(set_current_dir(&node_root).is_ok());
+
+            state_diff(&config_path, from_height, to_height, json);
+        }
+        Commands::Diverge {
+            mut config_path,
+            node_root,
+            other,
+            other_root,
+            hash_list,
+            export,
+        } => {
+            if !config_path.is_absolute() {
+                config_path = current_dir().unwrap().join(config_path);
+            }
+            let other = other.map(|path| current_dir().unwrap().join(path));
+            let other_root = other_root.map(|path| current_dir().unwrap().join(path));
+            let hash_list = hash_list.map(|path| current_dir().unwrap().join(path));
+            let export = export.map(|path| current_dir().unwrap().join(path));
+            assert!(set_current_dir(&node_root).is_ok());
+
+            if let Some(export) = export {
+                export_hash_list(&config_path, &export).await;
+            } else {
+                diverge(
+                    &config_path,
+                    other.as_deref(),
+                    other_root.as_deref(),
+                    hash_list.as_deref(),
+                )
+                .await;
+            }
+        }
+        Commands::Utxo {
+            command:
+                UtxoCommands::Rebuild {
+                    mut config_path,
+                    node_root,
+                    force,
+                },
+        } => {
+            if !config_path.is_absolute() {
+                config_path = current_dir().unwrap().join(config_path);
+            }
+            assert!(set_current_dir(&node_root).is_ok());
+            ensure_node_stopped(&config_path, force);
+
+            rebuild_utxo(&config_path).await;
+        }
+        Commands::SysconfigHistory {
+            mut config_path,
+            node_root,
+        } => {
+            if !config_path.is_absolute() {
+                config_path = current_dir().unwrap().join(config_path);
+            }
+            assert!(set_current_dir(&node_root).is_ok());
+
+            sysconfig_history(&config_path).await;
+        }
+        Commands::Sysconfig {
+            mut config_path,
+            node_root,
+            height,
+        } => {
+            if !config_path.is_absolute() {
+                config_path = current_dir().unwrap().join(config_path);
+            }
+            assert!(set_current_dir(&node_root).is_ok());
+
+            sysconfig(&config_path, height).await;
+        }
+        Commands::Cloud {
+            command:
+                CloudCommands::Status {
+                    mut config_path,
+                    node_root,
+                    samples,
+                },
+        } => {
+            if !config_path.is_absolute() {
+                config_path = current_dir().unwrap().join(config_path);
+            }
+            assert!(set_current_dir(&node_root).is_ok());
+
+            cloud_status(&config_path, samples).await;
+        }
+        Commands::Cloud {
+            command:
+                CloudCommands::Verify {
+                    mut config_path,
+                    node_root,
+                    range,
+                },
+        } => {
+            if !config_path.is_absolute() {
+                config_path = current_dir().unwrap().join(config_path);
+            }
+            assert!(set_current_dir(&node_root).is_ok());
+
+            cloud_verify(&config_path, range).await;
+        }
+        Commands::Cloud {
+            command:
+                CloudCommands::Restore {
+                    mut config_path,
+                    node_root,
+                    force,
+                    height,
+                },
+        } => {
+            if !config_path.is_absolute() {
+                config_path = current_dir().unwrap().join(config_path);
+            }
+            assert!(set_current_dir(&node_root).is_ok());
+            ensure_node_stopped(&config_path, force);
+
+            cloud_restore(&config_path, height).await;
+        }
+        Commands::Cloud {
+            command:
+                CloudCommands::Migrate {
+                    mut config_path,
+                    node_root,
+                    mut to,
+                    jobs,
+                },
+        } => {
+            if !config_path.is_absolute() {
+                config_path = current_dir().unwrap().join(config_path);
+            }
+            if !to.is_absolute() {
+                to = current_dir().unwrap().join(to);
+            }
+            assert!(set_current_dir(&node_root).is_ok());
+
+            cloud_migrate(&config_path, &to, jobs).await;
+        }
+        Commands::Bootstrap {
+            mut config_path,
+            node_root,
+            force,
+            mut from_backup,
+        } => {
+            if !config_path.is_absolute() {
+                config_path = current_dir().unwrap().join(config_path);
+            }
+            if !from_backup.is_absolute() {
+                from_backup = current_dir().unwrap().join(from_backup);
+            }
+            assert!(set_current_dir(&node_root).is_ok());
+            ensure_node_stopped(&config_path, force);
+
+            bootstrap(&config_path, &from_backup).await;
+        }
+        Commands::Compact {
+            mut config_path,
+            node_root,
+            force,
+            db,
+        } => {
+            if !config_path.is_absolute() {
+                config_path = current_dir().unwrap().join(config_path);
+            }
+            assert!(set_current_dir(&node_root).is_ok());
+            ensure_node_stopped(&config_path, force);
+
+            compact(&config_path, &db);
+        }
+        Commands::Cluster {
+            command:
+                ClusterCommands::Rollback {
+                    mut nodes,
+                    force,
+                    archive,
+                    height,
+                },
+        } => {
+            if !nodes.is_absolute() {
+                nodes = current_dir().unwrap().join(nodes);
+            }
+
+            cluster_rollback(&nodes, height, force, archive).await;
+        }
     }
 }
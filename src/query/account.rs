@@ -0,0 +1,127 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::hex_string;
+use crate::util::{executor_db_path, open_state_db, parse_hex, state_block_header};
+use cita_database::RocksDB;
+use cita_trie::{PatriciaTrie, Trie, DB};
+use cita_types::{Address, H256};
+use cita_vm::common;
+use cita_vm::state::{AccountDB, StateObject};
+use executor_evm::trie_db::{NodeType, TrieDb};
+use serde_json::json;
+use std::path::Path;
+use std::sync::Arc;
+
+pub fn parse_address(address: &str) -> Address {
+    let address_bytes = parse_hex(address);
+    if address_bytes.len() != 20 {
+        panic!("invalid address({}): must be 20 bytes", address);
+    }
+    Address::from_slice(&address_bytes)
+}
+
+pub fn load_state_object(
+    state_rocks_db: &Arc<RocksDB>,
+    state_root: &H256,
+    address: &Address,
+) -> Option<StateObject> {
+    let hasher = Arc::new(common::hash::get_hasher());
+    let state_db = Arc::new(TrieDb::new(Arc::clone(state_rocks_db), NodeType::Archive));
+    let pt = PatriciaTrie::from(state_db, hasher, &state_root.0).unwrap();
+    pt.get(address.as_bytes())
+        .unwrap()
+        .map(|st_data| StateObject::from_rlp(&st_data).unwrap())
+}
+
+pub fn account(
+    config_path: &Path,
+    address: &str,
+    height: Option<u64>,
+    show_code: bool,
+    show_abi: bool,
+    json: bool,
+) {
+    let address = parse_address(address);
+    let state_rocks_db = open_state_db(&executor_db_path(config_path));
+    let (block_hash, block_header) = state_block_header(&state_rocks_db, height);
+    let state_root = block_header.state_root();
+
+    let Some(st_obj) = load_state_object(&state_rocks_db, state_root, &address) else {
+        println!(
+            "account({}) not found at height({})",
+            hex_string(address.as_bytes()),
+            block_header.number()
+        );
+        return;
+    };
+
+    let account_db = AccountDB::new(
+        address,
+        Arc::new(TrieDb::new(Arc::clone(&state_rocks_db), NodeType::Archive)),
+    );
+    let code = if show_code && st_obj.code_hash != common::hash::NIL_DATA {
+        account_db.get(st_obj.code_hash.as_bytes()).unwrap()
+    } else {
+        None
+    };
+    let abi = if show_abi && st_obj.abi_hash != common::hash::NIL_DATA {
+        account_db.get(st_obj.abi_hash.as_bytes()).unwrap()
+    } else {
+        None
+    };
+
+    if json {
+        let value = json!({
+            "address": hex_string(address.as_bytes()),
+            "height": block_header.number(),
+            "block_hash": hex_string(block_hash.as_bytes()),
+            "state_root": hex_string(state_root.as_bytes()),
+            "nonce": st_obj.nonce.to_string(),
+            "balance": st_obj.balance.to_string(),
+            "code_hash": hex_string(st_obj.code_hash.as_bytes()),
+            "abi_hash": hex_string(st_obj.abi_hash.as_bytes()),
+            "storage_root": hex_string(st_obj.storage_root.as_bytes()),
+            "code": code.as_ref().map(|code| hex_string(code)),
+            "abi": abi.as_ref().map(|abi| String::from_utf8_lossy(abi).to_string()),
+        });
+        println!("{}", serde_json::to_string_pretty(&value).unwrap());
+        return;
+    }
+
+    println!("address: {}", hex_string(address.as_bytes()));
+    println!("height: {}", block_header.number());
+    println!("block_hash: {}", hex_string(block_hash.as_bytes()));
+    println!("state_root: {}", hex_string(state_root.as_bytes()));
+    println!("nonce: {}", st_obj.nonce);
+    println!("balance: {}", st_obj.balance);
+    println!("code_hash: {}", hex_string(st_obj.code_hash.as_bytes()));
+    println!("abi_hash: {}", hex_string(st_obj.abi_hash.as_bytes()));
+    println!(
+        "storage_root: {}",
+        hex_string(st_obj.storage_root.as_bytes())
+    );
+    if show_code {
+        match code {
+            Some(code) => println!("code: {}", hex_string(&code)),
+            None => println!("code: none"),
+        }
+    }
+    if show_abi {
+        match abi {
+            Some(abi) => println!("abi: {}", String::from_utf8_lossy(&abi)),
+            None => println!("abi: none"),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{hex_string, tx_hash, tx_json};
+use crate::storage::{storage_db, Storage};
+use crate::util::{bytes_to_u64, parse_hex};
+use cita_cloud_proto::blockchain::Block;
+use prost::Message;
+use serde_json::json;
+use std::path::Path;
+
+pub async fn block(config_path: &Path, block_id: &str, raw: bool, json: bool) {
+    let storage_db = storage_db(config_path).await;
+
+    // block_id is a height or a 0x prefixed block hash
+    let height = if block_id.starts_with("0x") {
+        let block_hash = parse_hex(block_id);
+        // region 8: block hash -> height
+        match storage_db.load(8, &block_hash).await {
+            Ok(height_bytes) => bytes_to_u64(&height_bytes),
+            Err(status) => panic!("block({}) not found: {}", block_id, status),
+        }
+    } else {
+        block_id
+            .parse::<u64>()
+            .map_err(|e| println!("invalid block height({block_id}): {e}"))
+            .unwrap()
+    };
+
+    let current_height = storage_db.current_height().await;
+    if height > current_height {
+        panic!(
+            "block height({}) > current height({})",
+            height, current_height
+        );
+    }
+
+    let block_bytes = storage_db
+        .load_full_block(height)
+        .await
+        .map_err(|e| println!("load block({height}) failed: {e}"))
+        .unwrap();
+    let block_hash = storage_db
+        .block_hash(height)
+        .await
+        .map_err(|e| println!("load block({height}) hash failed: {e}"))
+        .unwrap();
+
+    if raw {
+        println!("{}", hex_string(&block_bytes));
+        return;
+    }
+
+    let block = Block::decode(block_bytes.as_slice()).unwrap();
+    let header = block.header.clone().unwrap_or_default();
+    let body = block.body.clone().unwrap_or_default().body;
+
+    if json {
+        let value = json!({
+            "version": block.version,
+            "height": header.height,
+            "hash": hex_string(&block_hash),
+            "prevhash": hex_string(&header.prevhash),
+            "timestamp": header.timestamp,
+            "transactions_root": hex_string(&header.transactions_root),
+            "proposer": hex_string(&header.proposer),
+            "state_root": hex_string(&block.state_root),
+            "proof": hex_string(&block.proof),
+            "transactions": body.iter().map(tx_json).collect::<Vec<_>>(),
+        });
+        println!("{}", serde_json::to_string_pretty(&value).unwrap());
+        return;
+    }
+
+    println!("version: {}", block.version);
+    println!("height: {}", header.height);
+    println!("hash: {}", hex_string(&block_hash));
+    println!("prevhash: {}", hex_string(&header.prevhash));
+    println!("timestamp: {}", header.timestamp);
+    println!(
+        "transactions_root: {}",
+        hex_string(&header.transactions_root)
+    );
+    println!("proposer: {}", hex_string(&header.proposer));
+    println!("state_root: {}", hex_string(&block.state_root));
+    println!("proof: {} bytes", block.proof.len());
+    println!("tx count: {}", body.len());
+    for (index, raw_tx) in body.iter().enumerate() {
+        println!("  tx[{}]: {}", index, hex_string(&tx_hash(raw_tx)));
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod account;
+mod block;
+mod state_diff;
+mod storage_dump;
+mod sysconfig;
+mod tx;
+
+pub use account::account;
+pub use block::block;
+use cita_cloud_proto::blockchain::raw_transaction::Tx::{NormalTx, UtxoTx};
+use cita_cloud_proto::blockchain::{RawTransaction, Witness};
+use serde_json::{json, Value};
+pub use state_diff::state_diff;
+pub use storage_dump::{storage_dump, DumpFormat};
+pub use sysconfig::{sysconfig, sysconfig_history};
+pub use tx::tx;
+
+pub fn hex_string(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+pub fn tx_hash(raw_tx: &RawTransaction) -> Vec<u8> {
+    match raw_tx.tx.as_ref().unwrap() {
+        NormalTx(tx) => tx.transaction_hash.clone(),
+        UtxoTx(tx) => tx.transaction_hash.clone(),
+    }
+}
+
+fn witness_json(witness: &Witness) -> Value {
+    json!({
+        "signature": hex_string(&witness.signature),
+        "sender": hex_string(&witness.sender),
+    })
+}
+
+pub fn tx_json(raw_tx: &RawTransaction) -> Value {
+    match raw_tx.tx.as_ref().unwrap() {
+        NormalTx(tx) => {
+            let transaction = tx.transaction.as_ref().unwrap();
+            json!({
+                "type": "normal",
+                "hash": hex_string(&tx.transaction_hash),
+                "version": transaction.version,
+                "to": hex_string(&transaction.to),
+                "nonce": transaction.nonce,
+                "quota": transaction.quota,
+                "valid_until_block": transaction.valid_until_block,
+                "data": hex_string(&transaction.data),
+                "value": hex_string(&transaction.value),
+                "chain_id": hex_string(&transaction.chain_id),
+                "witness": tx.witness.as_ref().map(witness_json),
+            })
+        }
+        UtxoTx(tx) => {
+            let transaction = tx.transaction.as_ref().unwrap();
+            json!({
+                "type": "utxo",
+                "hash": hex_string(&tx.transaction_hash),
+                "version": transaction.version,
+                "pre_tx_hash": hex_string(&transaction.pre_tx_hash),
+                "output": hex_string(&transaction.output),
+                "lock_id": transaction.lock_id,
+                "witnesses": tx.witnesses.iter().map(witness_json).collect::<Vec<_>>(),
+            })
+        }
+    }
+}
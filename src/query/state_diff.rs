@@ -0,0 +1,270 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::hex_string;
+use crate::util::{executor_db_path, open_state_db, state_block_header};
+use cita_trie::DB;
+use cita_types::{Address, H256};
+use cita_vm::common;
+use cita_vm::state::{AccountDB, StateObject};
+use executor_evm::trie_db::{NodeType, TrieDb};
+use rlp::Rlp;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::Arc;
+
+// (key, value at height a, value at height b)
+type TrieChange = (Vec<u8>, Option<Vec<u8>>, Option<Vec<u8>>);
+
+// a child of a node which not loaded yet, or the rest of a leaf/extension node after some nibbles
+enum Child {
+    Ref(Vec<u8>),
+    Partial(Vec<u8>, usize),
+}
+
+#[derive(Default)]
+struct View {
+    value: Option<Vec<u8>>,
+    children: [Option<Child>; 16],
+}
+
+fn decode_hex_prefix(path: &[u8]) -> (Vec<u8>, bool) {
+    let nibbles: Vec<u8> = path.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect();
+    let is_leaf = nibbles[0] >= 2;
+    let is_odd = nibbles[0] % 2 == 1;
+    let skip = if is_odd { 1 } else { 2 };
+    (nibbles[skip..].to_vec(), is_leaf)
+}
+
+// a child reference is an inline node(list) or the hash of the node
+fn resolve<D: DB>(db: &D, reference: &[u8]) -> Option<Vec<u8>> {
+    let rlp = Rlp::new(reference);
+    if rlp.is_empty() {
+        None
+    } else if rlp.is_list() {
+        Some(reference.to_vec())
+    } else {
+        let hash = rlp.data().unwrap();
+        let node = db
+            .get(hash)
+            .unwrap()
+            .unwrap_or_else(|| panic!("trie node({}) missing", hex_string(hash)));
+        Some(node)
+    }
+}
+
+fn expand<D: DB>(db: &D, node: Option<(Vec<u8>, usize)>) -> View {
+    let mut view = View::default();
+    let Some((raw, offset)) = node else {
+        return view;
+    };
+    let rlp = Rlp::new(&raw);
+    match rlp.item_count().unwrap() {
+        2 => {
+            let (nibbles, is_leaf) = decode_hex_prefix(rlp.at(0).unwrap().data().unwrap());
+            if offset < nibbles.len() {
+                view.children[nibbles[offset] as usize] = Some(Child::Partial(raw, offset + 1));
+            } else if is_leaf {
+                view.value = Some(rlp.at(1).unwrap().data().unwrap().to_vec());
+            } else {
+                let child = resolve(db, rlp.at(1).unwrap().as_raw());
+                return expand(db, child.map(|child| (child, 0)));
+            }
+        }
+        17 => {
+            for (i, child) in view.children.iter_mut().enumerate() {
+                let item = rlp.at(i).unwrap();
+                if !item.is_empty() {
+                    *child = Some(Child::Ref(item.as_raw().to_vec()));
+                }
+            }
+            let value = rlp.at(16).unwrap();
+            if !value.is_empty() {
+                view.value = Some(value.data().unwrap().to_vec());
+            }
+        }
+        count => panic!("invalid trie node with {} items", count),
+    }
+    view
+}
+
+fn load_child<D: DB>(db: &D, child: Option<Child>) -> Option<(Vec<u8>, usize)> {
+    match child? {
+        Child::Ref(reference) => resolve(db, &reference).map(|node| (node, 0)),
+        Child::Partial(raw, offset) => Some((raw, offset)),
+    }
+}
+
+fn diff_node<D: DB>(
+    db: &D,
+    a: Option<(Vec<u8>, usize)>,
+    b: Option<(Vec<u8>, usize)>,
+    path: &mut Vec<u8>,
+    changes: &mut Vec<TrieChange>,
+) {
+    let view_a = expand(db, a);
+    let view_b = expand(db, b);
+
+    if view_a.value != view_b.value {
+        let key = path.chunks(2).map(|n| (n[0] << 4) | n[1]).collect();
+        changes.push((key, view_a.value, view_b.value));
+    }
+
+    for (i, (child_a, child_b)) in view_a.children.into_iter().zip(view_b.children).enumerate() {
+        // same hash means same subtree, skip it without loading
+        if let (Some(Child::Ref(ref_a)), Some(Child::Ref(ref_b))) = (&child_a, &child_b) {
+            if ref_a == ref_b {
+                continue;
+            }
+        }
+        let node_a = load_child(db, child_a);
+        let node_b = load_child(db, child_b);
+        if node_a.is_none() && node_b.is_none() {
+            continue;
+        }
+        path.push(i as u8);
+        diff_node(db, node_a, node_b, path, changes);
+        path.pop();
+    }
+}
+
+fn diff_trie<D: DB>(db: &D, root_a: &H256, root_b: &H256) -> Vec<TrieChange> {
+    let mut changes = Vec::new();
+    if root_a == root_b {
+        return changes;
+    }
+    let load_root = |root: &H256| {
+        if *root == common::hash::RLP_NULL {
+            return None;
+        }
+        let node = db
+            .get(root.as_bytes())
+            .unwrap()
+            .unwrap_or_else(|| panic!("trie root({}) missing", hex_string(root.as_bytes())));
+        Some((node, 0))
+    };
+    diff_node(
+        db,
+        load_root(root_a),
+        load_root(root_b),
+        &mut Vec::new(),
+        &mut changes,
+    );
+    changes
+}
+
+fn slot_value(value: &Option<Vec<u8>>) -> Value {
+    match value {
+        Some(value) => Value::String(hex_string(Rlp::new(value).data().unwrap())),
+        None => Value::Null,
+    }
+}
+
+fn text(value: &Value) -> &str {
+    value.as_str().unwrap_or("none")
+}
+
+fn account_json(st_obj: &StateObject) -> Value {
+    json!({
+        "nonce": st_obj.nonce.to_string(),
+        "balance": st_obj.balance.to_string(),
+        "code_hash": hex_string(st_obj.code_hash.as_bytes()),
+        "abi_hash": hex_string(st_obj.abi_hash.as_bytes()),
+        "storage_root": hex_string(st_obj.storage_root.as_bytes()),
+    })
+}
+
+pub fn state_diff(config_path: &Path, height_a: u64, height_b: u64, json: bool) {
+    let state_rocks_db = open_state_db(&executor_db_path(config_path));
+    let (_, header_a) = state_block_header(&state_rocks_db, Some(height_a));
+    let (_, header_b) = state_block_header(&state_rocks_db, Some(height_b));
+    let state_db = TrieDb::new(Arc::clone(&state_rocks_db), NodeType::Archive);
+
+    let mut report = Vec::new();
+    for (key, value_a, value_b) in
+        diff_trie(&state_db, header_a.state_root(), header_b.state_root())
+    {
+        let address = Address::from_slice(&key);
+        let st_a = value_a.map(|v| StateObject::from_rlp(&v).unwrap());
+        let st_b = value_b.map(|v| StateObject::from_rlp(&v).unwrap());
+
+        let storage_root = |st: &Option<StateObject>| {
+            st.as_ref()
+                .map(|st| st.storage_root)
+                .unwrap_or(common::hash::RLP_NULL)
+        };
+        let account_db = AccountDB::new(
+            address,
+            Arc::new(TrieDb::new(Arc::clone(&state_rocks_db), NodeType::Archive)),
+        );
+        let slots: Vec<Value> = diff_trie(&account_db, &storage_root(&st_a), &storage_root(&st_b))
+            .iter()
+            .map(|(slot, slot_a, slot_b)| {
+                json!({
+                    "key": hex_string(slot),
+                    "from": slot_value(slot_a),
+                    "to": slot_value(slot_b),
+                })
+            })
+            .collect();
+
+        let status = match (&st_a, &st_b) {
+            (None, Some(_)) => "added",
+            (Some(_), None) => "removed",
+            _ => "modified",
+        };
+        report.push(json!({
+            "address": hex_string(address.as_bytes()),
+            "status": status,
+            "from": st_a.as_ref().map(account_json),
+            "to": st_b.as_ref().map(account_json),
+            "storage": slots,
+        }));
+    }
+
+    if json {
+        let value = json!({
+            "from_height": height_a,
+            "to_height": height_b,
+            "accounts": report,
+        });
+        println!("{}", serde_json::to_string_pretty(&value).unwrap());
+        return;
+    }
+
+    println!("state diff: {} -> {}", height_a, height_b);
+    for account in report.iter() {
+        println!(
+            "account {} {}",
+            text(&account["address"]),
+            text(&account["status"])
+        );
+        for field in ["nonce", "balance", "code_hash", "abi_hash", "storage_root"] {
+            let from = &account["from"][field];
+            let to = &account["to"][field];
+            if from != to {
+                println!("  {}: {} -> {}", field, text(from), text(to));
+            }
+        }
+        for slot in account["storage"].as_array().unwrap() {
+            println!(
+                "  slot {}: {} -> {}",
+                text(&slot["key"]),
+                text(&slot["from"]),
+                text(&slot["to"])
+            );
+        }
+    }
+    println!("{} accounts changed", report.len());
+}
@@ -0,0 +1,95 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::account::{load_state_object, parse_address};
+use super::hex_string;
+use crate::util::{executor_db_path, open_state_db, parse_hex, state_block_header};
+use cita_trie::{PatriciaTrie, Trie};
+use cita_vm::common;
+use cita_vm::state::AccountDB;
+use clap::ValueEnum;
+use executor_evm::trie_db::{NodeType, TrieDb};
+use serde_json::json;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum DumpFormat {
+    Json,
+    Csv,
+}
+
+pub fn storage_dump(
+    config_path: &Path,
+    address: &str,
+    height: Option<u64>,
+    prefix: Option<String>,
+    format: DumpFormat,
+) {
+    let address = parse_address(address);
+    let prefix = prefix.map(|prefix| parse_hex(&prefix)).unwrap_or_default();
+    let state_rocks_db = open_state_db(&executor_db_path(config_path));
+    let (_, block_header) = state_block_header(&state_rocks_db, height);
+
+    let Some(st_obj) = load_state_object(&state_rocks_db, block_header.state_root(), &address)
+    else {
+        panic!(
+            "account({}) not found at height({})",
+            hex_string(address.as_bytes()),
+            block_header.number()
+        );
+    };
+
+    // walk the storage trie of the account, the value of each slot is rlp encoded
+    let mut slots = Vec::new();
+    if st_obj.storage_root != common::hash::RLP_NULL {
+        let account_db = Arc::new(AccountDB::new(
+            address,
+            Arc::new(TrieDb::new(Arc::clone(&state_rocks_db), NodeType::Archive)),
+        ));
+        let hasher = Arc::new(common::hash::get_hasher());
+        let pt = PatriciaTrie::from(account_db, hasher, st_obj.storage_root.as_bytes()).unwrap();
+        for (key, value) in pt.iter() {
+            if !key.starts_with(&prefix) {
+                continue;
+            }
+            let value = rlp::Rlp::new(&value).data().unwrap().to_vec();
+            slots.push((key, value));
+        }
+    }
+
+    match format {
+        DumpFormat::Json => {
+            let value = json!({
+                "address": hex_string(address.as_bytes()),
+                "height": block_header.number(),
+                "storage_root": hex_string(st_obj.storage_root.as_bytes()),
+                "slots": slots
+                    .iter()
+                    .map(|(key, value)| json!({
+                        "key": hex_string(key),
+                        "value": hex_string(value),
+                    }))
+                    .collect::<Vec<_>>(),
+            });
+            println!("{}", serde_json::to_string_pretty(&value).unwrap());
+        }
+        DumpFormat::Csv => {
+            println!("key,value");
+            for (key, value) in slots.iter() {
+                println!("{},{}", hex_string(key), hex_string(value));
+            }
+        }
+    }
+}
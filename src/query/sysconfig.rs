@@ -0,0 +1,162 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::hex_string;
+use crate::rollback::{
+    consensus_engine, is_init_pre_tx_hash, load_utxo_tx, lock_id_name, ConsensusEngine, UtxoWalk,
+    LOCK_IDS, LOCK_ID_ADMIN, LOCK_ID_BLOCK_INTERVAL, LOCK_ID_BLOCK_LIMIT, LOCK_ID_CHAIN_ID,
+    LOCK_ID_EMERGENCY_BRAKE, LOCK_ID_QUOTA_LIMIT, LOCK_ID_VALIDATORS, LOCK_ID_VERSION,
+};
+use crate::storage::{storage_db, Storage};
+use crate::util::{read_config, HASH_LEN};
+use std::path::Path;
+
+// address of admin and validators of raft
+const ADDR_BYTES_LEN: usize = 20;
+// bls public key of validators of overlord
+const BLS_PUBKEY_BYTES_LEN: usize = 48;
+
+fn decode_output(lock_id: u64, output: &[u8], engine: Option<ConsensusEngine>) -> String {
+    let be_u32 = |output: &[u8]| {
+        output
+            .get(..4)
+            .map(|b| u32::from_be_bytes(b.try_into().unwrap()).to_string())
+            .unwrap_or_else(|| hex_string(output))
+    };
+    match lock_id {
+        LOCK_ID_VERSION | LOCK_ID_BLOCK_INTERVAL | LOCK_ID_QUOTA_LIMIT => be_u32(output),
+        LOCK_ID_BLOCK_LIMIT => output
+            .get(..8)
+            .map(|b| u64::from_be_bytes(b.try_into().unwrap()).to_string())
+            .unwrap_or_else(|| hex_string(output)),
+        LOCK_ID_EMERGENCY_BRAKE => (!output.is_empty()).to_string(),
+        LOCK_ID_CHAIN_ID | LOCK_ID_ADMIN => hex_string(output),
+        LOCK_ID_VALIDATORS => {
+            // the format of validators depends on consensus engine
+            let len = match engine {
+                Some(ConsensusEngine::Raft) => ADDR_BYTES_LEN,
+                Some(ConsensusEngine::Overlord) => BLS_PUBKEY_BYTES_LEN,
+                None => return hex_string(output),
+            };
+            if output.len() % len != 0 {
+                return hex_string(output);
+            }
+            let validators: Vec<String> = output.chunks(len).map(hex_string).collect();
+            format!("[{}]", validators.join(", "))
+        }
+        _ => hex_string(output),
+    }
+}
+
+// (height, tx hash, decoded output) of every change of lock_id, newest first
+async fn lock_id_history<S: Storage>(
+    storage: &S,
+    lock_id: u64,
+    engine: Option<ConsensusEngine>,
+) -> Vec<(u64, Vec<u8>, String)> {
+    let mut history = Vec::new();
+    let Ok(data_or_tx_hash) = storage.load(0, &lock_id.to_be_bytes()).await else {
+        return history;
+    };
+    if data_or_tx_hash.len() != HASH_LEN as usize || lock_id == LOCK_ID_CHAIN_ID {
+        return history;
+    }
+
+    let mut walk = UtxoWalk::new(lock_id);
+    let mut tx_hash = data_or_tx_hash;
+    loop {
+        let step = match walk.visit(&tx_hash) {
+            Ok(()) => load_utxo_tx(storage, lock_id, &tx_hash).await,
+            Err(e) => Err(e),
+        };
+        let (tx_height, tx) = match step {
+            Ok(step) => step,
+            Err(e) => {
+                println!("history of lock_id({}) is incomplete: {}", lock_id, e);
+                break;
+            }
+        };
+        history.push((
+            tx_height,
+            tx_hash,
+            decode_output(lock_id, &tx.output, engine),
+        ));
+        if is_init_pre_tx_hash(&tx.pre_tx_hash) {
+            break;
+        }
+        tx_hash = tx.pre_tx_hash;
+    }
+    history
+}
+
+// system config in genesis, the names of keys are the same as lock_id names
+fn genesis_value(config_path: &Path, lock_id: u64) -> String {
+    read_config(config_path)
+        .get("system_config")
+        .and_then(|system_config| system_config.get(lock_id_name(lock_id)))
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+pub async fn sysconfig_history(config_path: &Path) {
+    let storage_db = storage_db(config_path).await;
+    let engine = consensus_engine(config_path);
+    for lock_id in LOCK_IDS {
+        println!("lock_id({}) {}:", lock_id, lock_id_name(lock_id));
+        println!("  genesis: {}", genesis_value(config_path, lock_id));
+        for (tx_height, tx_hash, output) in lock_id_history(&storage_db, lock_id, engine)
+            .await
+            .iter()
+            .rev()
+        {
+            println!(
+                "  height {} tx {}: {}",
+                tx_height,
+                hex_string(tx_hash),
+                output
+            );
+        }
+    }
+}
+
+pub async fn sysconfig(config_path: &Path, height: Option<u64>) {
+    let storage_db = storage_db(config_path).await;
+    let current_height = storage_db.current_height().await;
+    let height = height.unwrap_or(current_height);
+    if height > current_height {
+        panic!("height({}) > current height({})", height, current_height);
+    }
+    let engine = consensus_engine(config_path);
+    println!("system config at height {}:", height);
+    for lock_id in LOCK_IDS {
+        let history = lock_id_history(&storage_db, lock_id, engine).await;
+        match history
+            .iter()
+            .find(|(tx_height, _, _)| *tx_height <= height)
+        {
+            Some((tx_height, tx_hash, output)) => println!(
+                "  {}: {} (changed at height {} by tx {})",
+                lock_id_name(lock_id),
+                output,
+                tx_height,
+                hex_string(tx_hash)
+            ),
+            None => println!(
+                "  {}: {} (genesis)",
+                lock_id_name(lock_id),
+                genesis_value(config_path, lock_id)
+            ),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{hex_string, tx_json};
+use crate::storage::{storage_db, Storage};
+use crate::util::{bytes_to_u64, executor_db_path, parse_hex};
+use cita_cloud_proto::blockchain::raw_transaction::Tx::{NormalTx, UtxoTx};
+use cita_cloud_proto::blockchain::RawTransaction;
+use cita_database::{Config, DataCategory, Database, RocksDB, NUM_COLUMNS};
+use executor_evm::types::{
+    block_receipts::BlockReceipts,
+    db_indexes::{BlockNumber2Header, DbIndex, Hash2BlockReceipts},
+    header::Header,
+    receipt::Receipt,
+};
+use prost::Message;
+use serde_json::json;
+use std::path::Path;
+
+pub async fn tx(config_path: &Path, tx_hash: &str, json: bool) {
+    let storage_db = storage_db(config_path).await;
+    let tx_hash_bytes = parse_hex(tx_hash);
+
+    // region 7: tx hash -> height
+    let height = match storage_db.load(7, &tx_hash_bytes).await {
+        Ok(height_bytes) => bytes_to_u64(&height_bytes),
+        Err(status) => panic!("tx({}) not found: {}", tx_hash, status),
+    };
+    // region 1: tx hash -> raw tx
+    let raw_tx_bytes = storage_db
+        .load(1, &tx_hash_bytes)
+        .await
+        .map_err(|e| println!("load tx({tx_hash}) failed: {e}"))
+        .unwrap();
+    // region 9: tx hash -> index in block
+    let index = storage_db
+        .load(9, &tx_hash_bytes)
+        .await
+        .map(|index_bytes| bytes_to_u64(&index_bytes))
+        .ok();
+    let raw_tx = RawTransaction::decode(raw_tx_bytes.as_slice()).unwrap();
+    let receipt = index.and_then(|index| {
+        load_receipt(
+            &(executor_db_path(config_path) + "/nosql"),
+            height,
+            index as usize,
+        )
+    });
+
+    if json {
+        let value = json!({
+            "height": height,
+            "index": index,
+            "transaction": tx_json(&raw_tx),
+            "receipt": receipt.as_ref().map(|receipt| json!({
+                "quota_used": receipt.quota_used.to_string(),
+                "account_nonce": receipt.account_nonce.to_string(),
+                "logs": receipt.logs.len(),
+                "error": receipt.error.as_ref().map(|e| format!("{:?}", e)),
+            })),
+        });
+        println!("{}", serde_json::to_string_pretty(&value).unwrap());
+        return;
+    }
+
+    println!("hash: {}", tx_hash);
+    println!("height: {}", height);
+    match index {
+        Some(index) => println!("index: {}", index),
+        None => println!("index: not found"),
+    }
+    match raw_tx.tx.as_ref().unwrap() {
+        NormalTx(tx) => {
+            let transaction = tx.transaction.as_ref().unwrap();
+            println!("type: normal");
+            println!("to: {}", hex_string(&transaction.to));
+            println!("nonce: {}", transaction.nonce);
+            println!("quota: {}", transaction.quota);
+            println!("valid_until_block: {}", transaction.valid_until_block);
+            println!("value: {}", hex_string(&transaction.value));
+            println!("data: {}", hex_string(&transaction.data));
+            if let Some(witness) = &tx.witness {
+                println!("sender: {}", hex_string(&witness.sender));
+            }
+        }
+        UtxoTx(tx) => {
+            let transaction = tx.transaction.as_ref().unwrap();
+            println!("type: utxo");
+            println!("lock_id: {}", transaction.lock_id);
+            println!("pre_tx_hash: {}", hex_string(&transaction.pre_tx_hash));
+            println!("output: {}", hex_string(&transaction.output));
+        }
+    }
+    match receipt {
+        Some(receipt) => {
+            println!("receipt:");
+            println!("  quota_used: {}", receipt.quota_used);
+            println!("  account_nonce: {}", receipt.account_nonce);
+            println!("  logs: {}", receipt.logs.len());
+            match receipt.error {
+                Some(e) => println!("  error: {:?}", e),
+                None => println!("  error: none"),
+            }
+        }
+        None => println!("receipt: not found"),
+    }
+}
+
+fn load_receipt(chain_path: &str, height: u64, index: usize) -> Option<Receipt> {
+    if !Path::new(chain_path).exists() {
+        println!("executor chain db dir not exist, skip receipt");
+        return None;
+    }
+
+    let database_config = Config::with_category_num(NUM_COLUMNS);
+    let chain_db = RocksDB::open(chain_path, &database_config).ok()?;
+
+    let hkey = BlockNumber2Header(height).get_index().to_vec();
+    let header = chain_db
+        .get(Some(DataCategory::Headers), &hkey)
+        .unwrap_or(None)
+        .map(|hdr| rlp::decode::<Header>(&hdr).unwrap())?;
+
+    let rkey = Hash2BlockReceipts(header.hash()?).get_index().to_vec();
+    chain_db
+        .get(Some(DataCategory::Extra), &rkey)
+        .unwrap_or(None)
+        .map(|receipts| rlp::decode::<BlockReceipts>(&receipts).unwrap())
+        .and_then(|block_receipts| block_receipts.receipts.get(index).cloned())
+}
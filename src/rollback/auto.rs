@@ -0,0 +1,98 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::storage::{storage_db, Storage};
+use crate::util::{executor_db_path, open_state_db};
+use cita_database::{Config, DataCategory, Database, RocksDB, NUM_COLUMNS};
+use cita_trie::DB;
+use cita_types::H256;
+use executor_evm::trie_db::{NodeType, TrieDb};
+use executor_evm::types::{
+    db_indexes::{BlockNumber2Hash, BlockNumber2Header, DbIndex},
+    header::Header,
+};
+use std::path::Path;
+use std::sync::Arc;
+
+/// the highest height where storage, executor chain db and executor state db agree
+pub async fn consistent_height(config_path: &Path) -> Option<u64> {
+    let storage_db = storage_db(config_path).await;
+    let current_height = storage_db.current_height().await;
+
+    let executor_db_path = executor_db_path(config_path);
+    let state_rocks_db = open_state_db(&executor_db_path);
+    let state_db = TrieDb::new(Arc::clone(&state_rocks_db), NodeType::Archive);
+    let chain_path = executor_db_path + "/nosql";
+    if !Path::new(&chain_path).exists() {
+        panic!("executor chain db dir not exist");
+    }
+    let database_config = Config::with_category_num(NUM_COLUMNS);
+    let chain_db = RocksDB::open(&chain_path, &database_config).expect("DB file not found");
+
+    for height in (0..=current_height).rev() {
+        let Ok(storage_hash) = storage_db.block_hash(height).await else {
+            println!("height({}): block hash not found in storage", height);
+            continue;
+        };
+        let Some(chain_hash) = chain_db
+            .get(
+                Some(DataCategory::Headers),
+                &BlockNumber2Header(height).get_index().to_vec(),
+            )
+            .unwrap_or(None)
+            .and_then(|hdr| rlp::decode::<Header>(&hdr).unwrap().hash())
+        else {
+            println!("height({}): header not found in executor chain db", height);
+            continue;
+        };
+        let Some(state_hash) = state_rocks_db
+            .get(
+                Some(DataCategory::Extra),
+                &BlockNumber2Hash(height).get_index().to_vec(),
+            )
+            .unwrap_or(None)
+            .map(|h| rlp::decode::<H256>(&h).unwrap())
+        else {
+            println!(
+                "height({}): block hash not found in executor state db",
+                height
+            );
+            continue;
+        };
+        if storage_hash != chain_hash.as_bytes() || storage_hash != state_hash.as_bytes() {
+            println!(
+                "height({}): block hash mismatch, storage: 0x{}, executor chain db: 0x{}, executor state db: 0x{}",
+                height,
+                hex::encode(&storage_hash),
+                hex::encode(chain_hash.as_bytes()),
+                hex::encode(state_hash.as_bytes()),
+            );
+            continue;
+        }
+        let state_root = state_rocks_db
+            .get(Some(DataCategory::Headers), state_hash.as_bytes())
+            .unwrap_or(None)
+            .map(|header| *rlp::decode::<Header>(&header).unwrap().state_root());
+        match state_root {
+            Some(state_root) if state_db.get(state_root.as_bytes()).unwrap().is_some() => {
+                return Some(height);
+            }
+            _ => println!(
+                "height({}): state root not found in executor state db",
+                height
+            ),
+        }
+    }
+    None
+}
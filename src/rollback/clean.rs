@@ -0,0 +1,92 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::util::{read_config, CONTROLLER_WAL, OVERLORD_DATA, RAFT_DATA};
+use chrono::Local;
+use std::fs::{create_dir_all, remove_dir_all, rename};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+pub const ARCHIVE_DIR: &str = "./archive";
+
+#[derive(Clone, Copy, Debug)]
+pub enum ConsensusEngine {
+    Overlord,
+    Raft,
+}
+
+impl ConsensusEngine {
+    fn data_path(self) -> &'static str {
+        match self {
+            ConsensusEngine::Overlord => OVERLORD_DATA,
+            ConsensusEngine::Raft => RAFT_DATA,
+        }
+    }
+}
+
+pub fn consensus_engine(config_path: &Path) -> Option<ConsensusEngine> {
+    let config = read_config(config_path);
+    if config.contains_key("consensus_overlord") {
+        Some(ConsensusEngine::Overlord)
+    } else if config.contains_key("consensus_raft") {
+        Some(ConsensusEngine::Raft)
+    } else {
+        None
+    }
+}
+
+/// a timestamped dir under ARCHIVE_DIR to move cleaned data into
+pub fn archive_dir() -> PathBuf {
+    Path::new(ARCHIVE_DIR).join(Local::now().format("%Y%m%d%H%M%S").to_string())
+}
+
+/// remove path, or move it into archive_dir if given, and return what was done
+pub fn clean_dir(path: &str, archive_dir: Option<&Path>) -> String {
+    if !Path::new(path).exists() {
+        return format!("{} not exist, skip", path);
+    }
+    let res = match archive_dir {
+        Some(archive_dir) => {
+            let target = archive_dir.join(Path::new(path).file_name().unwrap());
+            create_dir_all(archive_dir)
+                .and_then(|_| rename(path, &target))
+                .map(|_| format!("archived {} to {}", path, target.display()))
+        }
+        None => remove_dir_all(path).map(|_| format!("removed {}", path)),
+    };
+    res.unwrap_or_else(|e| match e.kind() {
+        ErrorKind::NotFound => format!("{} not exist, skip", path),
+        _ => format!("clean {} failed: {}", path, e),
+    })
+}
+
+/// wal_path in controller config, default is CONTROLLER_WAL
+pub fn controller_wal_path(config_path: &Path) -> String {
+    read_config(config_path)
+        .get("controller")
+        .and_then(|controller| controller.get("wal_path"))
+        .and_then(|wal_path| wal_path.as_str())
+        .unwrap_or(CONTROLLER_WAL)
+        .to_string()
+}
+
+pub fn clean_consensus_data(config_path: &Path, archive_dir: Option<&Path>) -> Vec<String> {
+    match consensus_engine(config_path) {
+        Some(engine) => {
+            println!("consensus engine: {:?}", engine);
+            vec![clean_dir(engine.data_path(), archive_dir)]
+        }
+        None => vec!["consensus config not found, skip cleaning consensus data".to_string()],
+    }
+}
@@ -12,28 +12,158 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::util::{get_real_key, storage_db, StorageDb};
+use crate::cloud::backup_progress;
+use crate::query::tx_hash;
+use crate::storage::{cloud_storager, storage_db, Storage, StorageDb};
+use crate::util::{confirm, get_real_key};
+use cita_cloud_proto::blockchain::Block;
+use cita_cloud_proto::storage::Regions;
+use prost::Message;
 use std::path::Path;
+use storage_opendal::storager::Storager;
 
-pub async fn cloud_storage_rollback(config_path: &Path, rollback_cloud_height: u64) {
+/// what to do with cloud objects above the rollback height
+pub enum CloudPurge {
+    Keep,
+    Delete,
+    /// move objects under the prefix instead of deleting them
+    Quarantine(String),
+}
+
+fn region(region: Regions) -> u32 {
+    i32::from(region) as u32
+}
+
+// regions store_all_block_data writes, keyed by height, by block hash and by tx hash
+const HEIGHT_REGIONS: [Regions; 7] = [
+    Regions::Headers,
+    Regions::Bodies,
+    Regions::BlockHash,
+    Regions::Proof,
+    Regions::Result,
+    Regions::CompactBlock,
+    Regions::FullBlock,
+];
+const BLOCK_HASH_REGIONS: [Regions; 1] = [Regions::BlockHash2blockHeight];
+const TX_REGIONS: [Regions; 3] = [
+    Regions::Transactions,
+    Regions::TransactionHash2blockHeight,
+    Regions::TransactionIndex,
+];
+
+// keys of a block in cloud storage, stop at the first height without block hash
+async fn block_keys(cloud: &Storager, height: u64) -> Option<Vec<String>> {
+    let height_bytes = height.to_be_bytes();
+    let hash = cloud
+        .load(
+            &get_real_key(region(Regions::BlockHash), &height_bytes),
+            false,
+        )
+        .await
+        .ok()?;
+    let mut keys: Vec<String> = HEIGHT_REGIONS
+        .into_iter()
+        .map(|r| get_real_key(region(r), &height_bytes))
+        .collect();
+    keys.extend(
+        BLOCK_HASH_REGIONS
+            .into_iter()
+            .map(|r| get_real_key(region(r), &hash)),
+    );
+    if let Ok(block_bytes) = Storage::load_full_block(cloud, height).await {
+        if let Ok(block) = Block::decode(block_bytes.as_slice()) {
+            for raw_tx in block.body.unwrap_or_default().body {
+                let tx_hash = tx_hash(&raw_tx);
+                keys.extend(
+                    TX_REGIONS
+                        .into_iter()
+                        .map(|r| get_real_key(region(r), &tx_hash)),
+                );
+            }
+        }
+    }
+    Some(keys)
+}
+
+async fn purge_cloud_blocks(
+    cloud: &Storager,
+    height: u64,
+    purge: &CloudPurge,
+    dry_run: bool,
+    yes: bool,
+) {
+    let remote = &cloud.operator;
+    // blocks above an earlier rollback may still be there, so scan until no block found
+    let mut objects = Vec::new();
+    let mut h = height + 1;
+    while let Some(keys) = block_keys(cloud, h).await {
+        for key in keys {
+            if remote.stat(&key).await.is_ok() {
+                objects.push((h, key));
+            }
+        }
+        h += 1;
+    }
+    if objects.is_empty() {
+        println!("no cloud objects above height({})", height);
+        return;
+    }
+    println!(
+        "{} cloud objects of heights [{}, {}):",
+        objects.len(),
+        height + 1,
+        h
+    );
+    for (h, key) in &objects {
+        println!("  height {}: {}", h, key);
+    }
+    if dry_run {
+        return;
+    }
+    let action = match purge {
+        CloudPurge::Quarantine(prefix) => format!("move them to {}", prefix),
+        _ => "delete them".to_string(),
+    };
+    if !yes && !confirm(&format!("{}?", action)) {
+        println!("purge canceled");
+        return;
+    }
+
+    for (_, key) in &objects {
+        if let CloudPurge::Quarantine(prefix) = purge {
+            let value = remote.read(key).await.unwrap();
+            remote
+                .write(&format!("{}/{}", prefix.trim_end_matches('/'), key), value)
+                .await
+                .unwrap();
+        }
+        remote.delete(key).await.unwrap();
+    }
+    match purge {
+        CloudPurge::Quarantine(prefix) => {
+            println!("moved {} cloud objects to {}", objects.len(), prefix)
+        }
+        _ => println!("deleted {} cloud objects", objects.len()),
+    }
+}
+
+pub async fn cloud_storage_rollback(
+    config_path: &Path,
+    rollback_cloud_height: u64,
+    purge: CloudPurge,
+    dry_run: bool,
+    yes: bool,
+) {
     let storage_db = storage_db(config_path).await;
     let StorageDb::Opendal(storager) = &storage_db else {
         panic!("cloud rollback not support rocksdb")
     };
 
-    let remote = &storager
-        .next_storager
-        .as_ref()
-        .unwrap()
-        .next_storager
-        .as_ref()
-        .unwrap()
-        .operator;
-
-    if let Ok(remote_height_bytes) = remote.read(&get_real_key(0, &1u64.to_be_bytes())).await {
-        let mut buf: [u8; 8] = [0; 8];
-        buf.clone_from_slice(&remote_height_bytes.to_vec()[..8]);
-        let current_cloud_height = u64::from_be_bytes(buf);
+    let cloud = cloud_storager(storager, config_path);
+    let remote = &cloud.operator;
+
+    // rollback backup progress before purge, so cloud never claims blocks which are purged
+    if let Some((current_cloud_height, _)) = backup_progress(cloud).await {
         println!("current_cloud_height: {current_cloud_height}");
         println!("rollback_cloud_height: {rollback_cloud_height}");
         // value of key(0, 1) include backup height(u64) and backup index(u32)
@@ -43,21 +173,29 @@ pub async fn cloud_storage_rollback(config_path: &Path, rollback_cloud_height: u
                 "rollback_cloud_height({}) >= current_cloud_height({}), ignore rollback",
                 rollback_cloud_height, current_cloud_height
             );
-            return;
+        } else if dry_run {
+            println!(
+                "dry run, cloud backup height would rollback to {}",
+                rollback_cloud_height + 1
+            );
+        } else {
+            // value of key(0, 1) include backup height(u64) and backup index(u32)
+            // rollback to the height, we should set key(0, 1) as (height + 1, 0)
+            let mut buf = Vec::new();
+            let height = rollback_cloud_height + 1;
+            buf.extend_from_slice(&height.to_be_bytes());
+            buf.extend_from_slice(&[0u8; 4]);
+            remote
+                .write(&get_real_key(0, &1u64.to_be_bytes()), buf)
+                .await
+                .unwrap();
+            println!("cloud rollback done!");
         }
-
-        // value of key(0, 1) include backup height(u64) and backup index(u32)
-        // rollback to the height, we should set key(0, 1) as (height + 1, 0)
-        let mut buf = Vec::new();
-        let height = rollback_cloud_height + 1;
-        buf.extend_from_slice(&height.to_be_bytes());
-        buf.extend_from_slice(&[0u8; 4]);
-        remote
-            .write(&get_real_key(0, &1u64.to_be_bytes()), buf)
-            .await
-            .unwrap();
-        println!("cloud rollback done!");
     } else {
         println!("backup hasn't started");
     }
+
+    if !matches!(purge, CloudPurge::Keep) {
+        purge_cloud_blocks(cloud, rollback_cloud_height, &purge, dry_run, yes).await;
+    }
 }
@@ -0,0 +1,139 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::storage::{storage_db, Storage, StorageDb};
+use crate::util::parse_hex;
+use std::collections::BTreeMap;
+use std::env::set_current_dir;
+use std::fs;
+use std::path::Path;
+
+// the node to compare with: another node's storage or an exported hash list
+enum Reference {
+    Node(StorageDb),
+    HashList(BTreeMap<u64, Vec<u8>>),
+}
+
+impl Reference {
+    async fn current_height(&self) -> u64 {
+        match self {
+            Reference::Node(storage_db) => storage_db.current_height().await,
+            Reference::HashList(hashes) => *hashes.keys().last().expect("hash list is empty"),
+        }
+    }
+
+    async fn block_hash(&self, height: u64) -> Option<Vec<u8>> {
+        match self {
+            Reference::Node(storage_db) => block_hash(storage_db, height).await,
+            Reference::HashList(hashes) => hashes.get(&height).cloned(),
+        }
+    }
+}
+
+async fn block_hash(storage_db: &StorageDb, height: u64) -> Option<Vec<u8>> {
+    storage_db.block_hash(height).await.ok()
+}
+
+// each line of hash list is `<height> <0x prefixed block hash>`
+fn read_hash_list(path: &Path) -> BTreeMap<u64, Vec<u8>> {
+    let s = fs::read_to_string(path)
+        .map_err(|e| println!("read hash list err: {e}"))
+        .unwrap();
+    s.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let (height, hash) = line
+                .trim()
+                .split_once(char::is_whitespace)
+                .unwrap_or_else(|| panic!("invalid hash list line: {}", line));
+            let height = height
+                .parse::<u64>()
+                .unwrap_or_else(|_| panic!("invalid hash list line: {}", line));
+            (height, parse_hex(hash.trim()))
+        })
+        .collect()
+}
+
+pub async fn export_hash_list(config_path: &Path, path: &Path) {
+    let storage_db = storage_db(config_path).await;
+    let current_height = storage_db.current_height().await;
+    let mut s = String::new();
+    for height in 0..=current_height {
+        print!("\rexporting: {}", height);
+        let hash = block_hash(&storage_db, height)
+            .await
+            .unwrap_or_else(|| panic!("block hash of height({}) not found", height));
+        s.push_str(&format!("{} 0x{}\n", height, hex::encode(hash)));
+    }
+    fs::write(path, s)
+        .map_err(|e| println!("write hash list err: {e}"))
+        .unwrap();
+    println!("\nexport hash list done!");
+}
+
+pub async fn diverge(
+    config_path: &Path,
+    other_config_path: Option<&Path>,
+    other_node_root: Option<&Path>,
+    hash_list: Option<&Path>,
+) {
+    let local = storage_db(config_path).await;
+    let reference = if let Some(hash_list) = hash_list {
+        Reference::HashList(read_hash_list(hash_list))
+    } else {
+        // storage path in other config is relative to other node root
+        assert!(set_current_dir(other_node_root.unwrap()).is_ok());
+        Reference::Node(storage_db(other_config_path.unwrap()).await)
+    };
+
+    let current_height = local.current_height().await;
+    let other_height = reference.current_height().await;
+    println!("current height: {}", current_height);
+    println!("other current height: {}", other_height);
+
+    let same_at = |local: Option<Vec<u8>>, other: Option<Vec<u8>>| match (local, other) {
+        (Some(local), Some(other)) => local == other,
+        _ => false,
+    };
+
+    let top = current_height.min(other_height);
+    if same_at(
+        block_hash(&local, top).await,
+        reference.block_hash(top).await,
+    ) {
+        println!("no divergence found up to height {}", top);
+        return;
+    }
+    if !same_at(block_hash(&local, 0).await, reference.block_hash(0).await) {
+        println!("genesis block differs, they are not the same chain");
+        return;
+    }
+
+    // block hashes form a chain, so once diverged they never meet again
+    // find the first divergent height in (0, top]
+    let (mut low, mut high) = (0, top);
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        if same_at(
+            block_hash(&local, mid).await,
+            reference.block_hash(mid).await,
+        ) {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    println!("first divergent height: {}", high);
+    println!("suggested rollback height: {}", low);
+}
@@ -29,6 +29,48 @@ pub fn executor_rollback(executor_db_path: &str, height: u64) {
     println!("executor rollback done!");
 }
 
+/// whether current hash of executor state db or chain db is above the block of height
+/// the controller executes a block before storing it, so after a crash executor can be ahead of storage
+pub fn executor_ahead(executor_db_path: &str, height: u64) -> bool {
+    let state_path = executor_db_path.to_owned() + "/statedb";
+    let chain_path = executor_db_path.to_owned() + "/nosql";
+    if !Path::new(&state_path).exists() || !Path::new(&chain_path).exists() {
+        panic!("executor db dir not exist");
+    }
+    let database_config = Config::with_category_num(NUM_COLUMNS);
+    let current_hash = |db: &RocksDB| {
+        db.get(Some(DataCategory::Extra), &CurrentHash.get_index().to_vec())
+            .unwrap_or(None)
+            .map(|h| rlp::decode::<H256>(&h).unwrap())
+    };
+
+    let state_db = RocksDB::open(&state_path, &database_config).unwrap();
+    let state_hash = state_db
+        .get(
+            Some(DataCategory::Extra),
+            &BlockNumber2Hash(height).get_index().to_vec(),
+        )
+        .unwrap_or(None)
+        .map(|h| rlp::decode::<H256>(&h).unwrap());
+    let chain_db = RocksDB::open(&chain_path, &database_config).expect("DB file not found");
+    let chain_hash = chain_db
+        .get(
+            Some(DataCategory::Headers),
+            &BlockNumber2Header(height).get_index().to_vec(),
+        )
+        .unwrap_or(None)
+        .and_then(|hdr| rlp::decode::<Header>(&hdr).unwrap().hash());
+
+    match (state_hash, chain_hash) {
+        (Some(state_hash), Some(chain_hash)) => {
+            current_hash(&state_db) != Some(state_hash)
+                || current_hash(&chain_db) != Some(chain_hash)
+        }
+        // executor hasn't reached height, rollback can't help
+        _ => false,
+    }
+}
+
 fn state_rollback(state_path: &str, height: u64) {
     if !Path::new(&state_path).exists() {
         panic!("executor state_db dir not exist");
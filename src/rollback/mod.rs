@@ -12,42 +12,93 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod auto;
+mod clean;
 mod cloud_rollback;
+mod diverge;
 mod executor_rollback;
-mod rocksdb_rollback;
+mod resolve;
 mod storage_rollback;
+mod utxo_rebuild;
+mod utxo_rollback;
 
-use crate::util::{executor_db_path, read_current_height, storage_db, StorageDb};
-pub use cloud_rollback::cloud_storage_rollback;
+use crate::storage::{storage_db, Storage};
+use crate::util::executor_db_path;
+pub use auto::consistent_height;
+use clean::archive_dir;
+pub use clean::{
+    clean_consensus_data, clean_dir, consensus_engine, controller_wal_path, ConsensusEngine,
+};
+pub use cloud_rollback::{cloud_storage_rollback, CloudPurge};
+pub use diverge::{diverge, export_hash_list};
+use executor_rollback::executor_ahead;
 pub use executor_rollback::executor_rollback;
-pub use rocksdb_rollback::rocksdb_rollback;
+pub use resolve::{height_before_time, height_before_tx, height_of_block};
 use std::path::Path;
 pub use storage_rollback::storage_rollback;
+pub use utxo_rebuild::{rebuild_utxo, utxo_rebuild};
+pub use utxo_rollback::{
+    is_init_pre_tx_hash, load_utxo_tx, lock_id_name, UtxoWalk, LOCK_IDS, LOCK_ID_ADMIN,
+    LOCK_ID_BLOCK_INTERVAL, LOCK_ID_BLOCK_LIMIT, LOCK_ID_CHAIN_ID, LOCK_ID_EMERGENCY_BRAKE,
+    LOCK_ID_QUOTA_LIMIT, LOCK_ID_VALIDATORS, LOCK_ID_VERSION,
+};
 
-pub async fn rollback(config_path: &Path, height: u64, clean_consensus_data: bool) {
+/// returns whether anything was rolled back
+pub async fn rollback(
+    config_path: &Path,
+    height: u64,
+    clean_consensus: bool,
+    rebuild_utxo: bool,
+    archive: bool,
+    keep_controller_wal: bool,
+) -> bool {
     let storage_db = storage_db(config_path).await;
 
-    let current_height = read_current_height(&storage_db).await;
+    let current_height = storage_db.current_height().await;
     println!("current height: {}", current_height);
     println!("rollback height: {}", height);
-    if height >= current_height {
+    let executor_db_path = &executor_db_path(config_path);
+    // storage at height may still have executor ahead of it
+    if height > current_height
+        || (height == current_height && !executor_ahead(executor_db_path, height))
+    {
         println!(
             "rollback height({}) >= current height({}), ignore rollback",
             height, current_height
         );
-        return;
+        return false;
     }
 
     // rollback storage
-    if let StorageDb::Opendal(storager) = &storage_db {
-        storage_rollback(storager, height, clean_consensus_data).await;
-    } else if let StorageDb::RocksDB(storager) = &storage_db {
-        rocksdb_rollback(storager, height, true, clean_consensus_data);
+    if height < current_height {
+        storage_rollback(&storage_db, height, rebuild_utxo).await;
     } else {
-        panic!("not support storage type");
+        println!(
+            "storage is at height({}) but executor is ahead, only rollback executor",
+            height
+        );
     }
 
     // rollback executor
-    let executor_db_path = &executor_db_path(config_path);
     executor_rollback(executor_db_path, height);
+
+    // remove or archive consensus data and controller wal
+    // only after rollback succeed, a failed rollback leaves them untouched
+    let archive_dir = archive.then(archive_dir);
+    let mut cleaned = Vec::new();
+    if clean_consensus {
+        cleaned.extend(clean_consensus_data(config_path, archive_dir.as_deref()));
+    }
+    // controller wal holds blocks above height, it must go with both storage backends
+    let controller_wal = controller_wal_path(config_path);
+    if keep_controller_wal {
+        cleaned.push(format!("keep {}", controller_wal));
+    } else {
+        cleaned.push(clean_dir(&controller_wal, archive_dir.as_deref()));
+    }
+
+    for record in cleaned {
+        println!("clean: {}", record);
+    }
+    true
 }
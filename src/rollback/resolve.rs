@@ -0,0 +1,87 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::storage::{storage_db, Storage, StorageDb};
+use crate::util::{bytes_to_u64, parse_hex};
+use chrono::DateTime;
+use cita_cloud_proto::blockchain::CompactBlock;
+use prost::Message;
+use std::path::Path;
+
+/// the height of the block with block_hash
+pub async fn height_of_block(config_path: &Path, block_hash: &str) -> u64 {
+    let storage_db = storage_db(config_path).await;
+    // region 8: block hash -> height
+    match storage_db.load(8, &parse_hex(block_hash)).await {
+        Ok(height_bytes) => bytes_to_u64(&height_bytes),
+        Err(status) => panic!("block({}) not found: {}", block_hash, status),
+    }
+}
+
+/// the height just before the block which contains tx_hash
+pub async fn height_before_tx(config_path: &Path, tx_hash: &str) -> u64 {
+    let storage_db = storage_db(config_path).await;
+    // region 7: tx hash -> height
+    let tx_height = match storage_db.load(7, &parse_hex(tx_hash)).await {
+        Ok(height_bytes) => bytes_to_u64(&height_bytes),
+        Err(status) => panic!("tx({}) not found: {}", tx_hash, status),
+    };
+    println!("tx({}) height: {}", tx_hash, tx_height);
+    if tx_height == 0 {
+        panic!("tx({}) is in genesis block", tx_hash);
+    }
+    tx_height - 1
+}
+
+async fn block_timestamp(storage_db: &StorageDb, height: u64) -> u64 {
+    // region 10: height -> compact block
+    let compact_block_bytes = storage_db
+        .load(10, &height.to_be_bytes())
+        .await
+        .map_err(|e| println!("load block({height}) failed: {e}"))
+        .unwrap();
+    CompactBlock::decode(compact_block_bytes.as_slice())
+        .unwrap()
+        .header
+        .unwrap()
+        .timestamp
+}
+
+/// the highest height whose block timestamp is before time
+pub async fn height_before_time(config_path: &Path, time: &str) -> u64 {
+    let timestamp = DateTime::parse_from_rfc3339(time)
+        .map_err(|e| println!("invalid RFC3339 time({time}): {e}"))
+        .unwrap()
+        .timestamp_millis() as u64;
+    let storage_db = storage_db(config_path).await;
+    let current_height = storage_db.current_height().await;
+
+    if block_timestamp(&storage_db, 0).await >= timestamp {
+        panic!("no block before {}", time);
+    }
+    if block_timestamp(&storage_db, current_height).await < timestamp {
+        return current_height;
+    }
+    // timestamp of height low < time <= timestamp of height high
+    let (mut low, mut high) = (0, current_height);
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        if block_timestamp(&storage_db, mid).await < timestamp {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
@@ -0,0 +1,101 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::utxo_rollback::{lock_id_name, LOCK_IDS, LOCK_ID_CHAIN_ID};
+use crate::storage::{storage_db, Storage};
+use crate::util::HASH_LEN;
+use cita_cloud_proto::blockchain::raw_transaction::Tx::UtxoTx;
+use cita_cloud_proto::blockchain::Block;
+use prost::Message;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub async fn utxo_rebuild<S: Storage>(storage: &S, height: u64) {
+    // the last utxo tx of every lock_id at or below height
+    let mut last_txs = BTreeMap::new();
+    for h in 0..=height {
+        print!("\rscanning: {}/{}", h, height);
+        let block_bytes = storage
+            .load_full_block(h)
+            .await
+            .map_err(|e| println!("\nload block({h}) failed: {e}"))
+            .unwrap();
+        let block = Block::decode(block_bytes.as_slice()).unwrap();
+        for raw_tx in block.body.unwrap_or_default().body {
+            let Some(UtxoTx(utxo_tx)) = &raw_tx.tx else {
+                continue;
+            };
+            let lock_id = utxo_tx.transaction.as_ref().unwrap().lock_id;
+            if !LOCK_IDS.contains(&lock_id) {
+                continue;
+            }
+            let tx_hash = utxo_tx.transaction_hash.clone();
+
+            // repair the index which utxo rollback walks
+            // region 1: tx hash -> raw tx, region 7: tx hash -> height
+            let raw_tx_bytes = raw_tx.encode_to_vec();
+            storage.store(1, &tx_hash, &raw_tx_bytes).await.unwrap();
+            storage.store(7, &tx_hash, &h.to_be_bytes()).await.unwrap();
+            last_txs.insert(lock_id, (h, tx_hash));
+        }
+    }
+    println!();
+
+    for lock_id in LOCK_IDS {
+        match last_txs.get(&lock_id) {
+            Some((tx_height, tx_hash)) => {
+                println!(
+                    "lock_id({}) {}: tx(0x{}) at height {}",
+                    lock_id,
+                    lock_id_name(lock_id),
+                    hex::encode(tx_hash),
+                    tx_height
+                );
+                storage
+                    .store(0, &lock_id.to_be_bytes(), tx_hash)
+                    .await
+                    .unwrap();
+            }
+            None => {
+                // a tx hash stored means it changed after height, restore the init state
+                let current = storage.load(0, &lock_id.to_be_bytes()).await.ok();
+                if current.is_some_and(|current| {
+                    current.len() == HASH_LEN as usize && lock_id != LOCK_ID_CHAIN_ID
+                }) {
+                    println!(
+                        "lock_id({}) {}: no utxo tx, delete content to be init state",
+                        lock_id,
+                        lock_id_name(lock_id)
+                    );
+                    storage.delete(0, &lock_id.to_be_bytes()).await.unwrap();
+                } else {
+                    println!(
+                        "lock_id({}) {}: never change from genesis",
+                        lock_id,
+                        lock_id_name(lock_id)
+                    );
+                }
+            }
+        }
+    }
+    println!("utxo rebuild done!");
+}
+
+// lock_ids are rebuilt at current height, use rollback --rebuild-utxo for a lower height
+pub async fn rebuild_utxo(config_path: &Path) {
+    let storage_db = storage_db(config_path).await;
+    let current_height = storage_db.current_height().await;
+    println!("rebuild height: {}", current_height);
+    utxo_rebuild(&storage_db, current_height).await;
+}
@@ -0,0 +1,250 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::storage::Storage;
+use crate::util::{bytes_to_u64, HASH_LEN};
+use cita_cloud_proto::blockchain::raw_transaction::Tx::UtxoTx;
+use cita_cloud_proto::blockchain::{RawTransaction, UtxoTransaction};
+use prost::Message;
+use std::collections::HashSet;
+
+// lock_ids of system config stored in region 0, keep same with controller
+pub const LOCK_ID_VERSION: u64 = 1_000;
+pub const LOCK_ID_CHAIN_ID: u64 = 1_001;
+pub const LOCK_ID_ADMIN: u64 = 1_002;
+pub const LOCK_ID_BLOCK_INTERVAL: u64 = 1_003;
+pub const LOCK_ID_VALIDATORS: u64 = 1_004;
+pub const LOCK_ID_EMERGENCY_BRAKE: u64 = 1_005;
+pub const LOCK_ID_QUOTA_LIMIT: u64 = 1_006;
+pub const LOCK_ID_BLOCK_LIMIT: u64 = 1_007;
+pub const LOCK_ID_BUTTON: u64 = 1_008;
+
+pub const LOCK_IDS: std::ops::Range<u64> = LOCK_ID_VERSION..LOCK_ID_BUTTON;
+
+pub fn lock_id_name(lock_id: u64) -> &'static str {
+    match lock_id {
+        LOCK_ID_VERSION => "version",
+        LOCK_ID_CHAIN_ID => "chain_id",
+        LOCK_ID_ADMIN => "admin",
+        LOCK_ID_BLOCK_INTERVAL => "block_interval",
+        LOCK_ID_VALIDATORS => "validators",
+        LOCK_ID_EMERGENCY_BRAKE => "emergency_brake",
+        LOCK_ID_QUOTA_LIMIT => "quota_limit",
+        LOCK_ID_BLOCK_LIMIT => "block_limit",
+        _ => "unknown",
+    }
+}
+
+fn value_string(value: &Option<Vec<u8>>) -> String {
+    match value {
+        Some(value) => format!("0x{}", hex::encode(value)),
+        None => "none".to_string(),
+    }
+}
+
+// a lock_id never changes more times than this, a longer chain must be corrupt
+const MAX_UTXO_CHAIN_DEPTH: usize = 100_000;
+
+/// pre_tx_hash of the first utxo tx of a lock_id
+pub fn is_init_pre_tx_hash(pre_tx_hash: &[u8]) -> bool {
+    pre_tx_hash == [0u8; 33]
+}
+
+/// guard against cycles and unbounded depth when following pre_tx_hash of a lock_id
+pub struct UtxoWalk {
+    lock_id: u64,
+    visited: HashSet<Vec<u8>>,
+}
+
+impl UtxoWalk {
+    pub fn new(lock_id: u64) -> Self {
+        Self {
+            lock_id,
+            visited: HashSet::new(),
+        }
+    }
+
+    pub fn visit(&mut self, tx_hash: &[u8]) -> Result<(), String> {
+        if self.visited.len() >= MAX_UTXO_CHAIN_DEPTH {
+            return Err(format!(
+                "lock_id({}) utxo chain exceeds max depth({}) at tx(0x{})",
+                self.lock_id,
+                MAX_UTXO_CHAIN_DEPTH,
+                hex::encode(tx_hash)
+            ));
+        }
+        if !self.visited.insert(tx_hash.to_vec()) {
+            return Err(format!(
+                "lock_id({}) utxo chain has a cycle at tx(0x{})",
+                self.lock_id,
+                hex::encode(tx_hash)
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn utxo_chain_broke(lock_id: u64, tx_hash: &[u8], reason: String) -> String {
+    format!(
+        "lock_id({}) utxo chain broke at tx(0x{}): {}",
+        lock_id,
+        hex::encode(tx_hash),
+        reason
+    )
+}
+
+// height of an utxo tx of lock_id
+async fn load_utxo_tx_height<S: Storage>(
+    storage: &S,
+    lock_id: u64,
+    tx_hash: &[u8],
+) -> Result<u64, String> {
+    // region 7: tx hash -> height
+    storage
+        .load(7, tx_hash)
+        .await
+        .map(|height_bytes| bytes_to_u64(&height_bytes))
+        .map_err(|e| utxo_chain_broke(lock_id, tx_hash, format!("load tx height failed: {e}")))
+}
+
+/// height and content of an utxo tx of lock_id
+pub async fn load_utxo_tx<S: Storage>(
+    storage: &S,
+    lock_id: u64,
+    tx_hash: &[u8],
+) -> Result<(u64, UtxoTransaction), String> {
+    let broke = |reason: String| utxo_chain_broke(lock_id, tx_hash, reason);
+    let height = load_utxo_tx_height(storage, lock_id, tx_hash).await?;
+    // region 1: tx hash -> raw tx
+    let raw_tx_bytes = storage
+        .load(1, tx_hash)
+        .await
+        .map_err(|e| broke(format!("load tx failed: {e}")))?;
+    let raw_tx = RawTransaction::decode(raw_tx_bytes.as_slice())
+        .map_err(|e| broke(format!("decode tx failed: {e}")))?;
+    match raw_tx.tx {
+        Some(UtxoTx(tx)) => tx
+            .transaction
+            .map(|tx| (height, tx))
+            .ok_or_else(|| broke("utxo tx is empty".to_string())),
+        _ => Err(broke("tx is not utxo".to_string())),
+    }
+}
+
+enum UtxoTarget {
+    Keep,
+    Init,
+    Tx(Vec<u8>),
+}
+
+// find the last utxo tx of lock_id at or below height
+async fn utxo_target<S: Storage>(
+    storage: &S,
+    lock_id: u64,
+    tx_hash: Vec<u8>,
+    height: u64,
+) -> Result<UtxoTarget, String> {
+    let mut walk = UtxoWalk::new(lock_id);
+    let mut tx_hash = tx_hash;
+    let mut modify = false;
+    loop {
+        walk.visit(&tx_hash)?;
+        // the raw tx is only needed to step back, a tx at or below height stops here
+        if load_utxo_tx_height(storage, lock_id, &tx_hash).await? <= height {
+            return Ok(if modify {
+                UtxoTarget::Tx(tx_hash)
+            } else {
+                UtxoTarget::Keep
+            });
+        }
+        let (_, tx) = load_utxo_tx(storage, lock_id, &tx_hash).await?;
+        if is_init_pre_tx_hash(&tx.pre_tx_hash) {
+            return Ok(UtxoTarget::Init);
+        }
+        tx_hash = tx.pre_tx_hash;
+        modify = true;
+    }
+}
+
+pub async fn utxo_rollback<S: Storage>(storage: &S, height: u64) {
+    // resolve every lock_id before writing, so a broken chain leaves storage untouched
+    let mut targets = Vec::new();
+    let mut errors = Vec::new();
+    for lock_id in LOCK_IDS {
+        let before = storage.load(0, &lock_id.to_be_bytes()).await.ok();
+        let target = match &before {
+            Some(data_or_tx_hash) => {
+                if data_or_tx_hash.len() == HASH_LEN as usize && lock_id != LOCK_ID_CHAIN_ID {
+                    match utxo_target(storage, lock_id, data_or_tx_hash.clone(), height).await {
+                        Ok(target) => Some(target),
+                        Err(e) => {
+                            errors.push(e);
+                            continue;
+                        }
+                    }
+                } else {
+                    println!("lock_id({}) never change from genesis", lock_id);
+                    None
+                }
+            }
+            None => {
+                println!(
+                    "load utxo({}) failed. Is this a new chain or version lower than v6.3.2",
+                    lock_id
+                );
+                None
+            }
+        };
+        targets.push((lock_id, before, target));
+    }
+    if !errors.is_empty() {
+        panic!("utxo rollback aborted:\n{}", errors.join("\n"));
+    }
+
+    let mut report = Vec::new();
+    for (lock_id, before, target) in targets {
+        match target {
+            None => {}
+            Some(UtxoTarget::Keep) => println!("lock_id({}) keep change", lock_id),
+            Some(UtxoTarget::Init) => {
+                println!("delete lock_id({}) content to be init state", lock_id);
+                storage.delete(0, &lock_id.to_be_bytes()).await.unwrap();
+            }
+            Some(UtxoTarget::Tx(tx_hash)) => {
+                println!(
+                    "modify lock_id({}) with tx_hash(0x{})",
+                    lock_id,
+                    hex::encode(&tx_hash)
+                );
+                storage
+                    .store(0, &lock_id.to_be_bytes(), &tx_hash)
+                    .await
+                    .unwrap();
+            }
+        }
+        let after = storage.load(0, &lock_id.to_be_bytes()).await.ok();
+        report.push((lock_id, before, after));
+    }
+
+    println!("utxo rollback report:");
+    for (lock_id, before, after) in report {
+        println!(
+            "lock_id({}) {}: {} -> {}",
+            lock_id,
+            lock_id_name(lock_id),
+            value_string(&before),
+            value_string(&after)
+        );
+    }
+}
@@ -0,0 +1,367 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::util::{bytes_to_u64, get_real_key, read_config};
+use cita_cloud_proto::blockchain::CompactBlock;
+use cita_cloud_proto::status_code::StatusCodeEnum;
+use opendal::services::{Azblob, Cos, Obs, Oss, S3};
+use opendal::Operator;
+use prost::Message;
+use std::fs;
+use std::path::{Path, PathBuf};
+use storage_opendal::{config::StorageConfig as OpendalConfig, storager::Storager};
+use storage_rocksdb::{config::StorageConfig as RocksdbConfig, db::DB};
+
+/// storage data access by region and key, the same for storage_rocksdb and storage_opendal
+pub trait Storage {
+    async fn load(&self, region: u32, key: &[u8]) -> Result<Vec<u8>, StatusCodeEnum>;
+    async fn store(&self, region: u32, key: &[u8], value: &[u8]) -> Result<(), StatusCodeEnum>;
+    async fn delete(&self, region: u32, key: &[u8]) -> Result<(), StatusCodeEnum>;
+    async fn load_full_block(&self, height: u64) -> Result<Vec<u8>, StatusCodeEnum>;
+    /// rollback current height and everything derived from it
+    async fn set_current_height(&self, height: u64) -> Result<(), StatusCodeEnum>;
+
+    async fn current_height(&self) -> u64 {
+        let current_height_bytes = self.load(0, &0u64.to_be_bytes()).await.unwrap();
+        bytes_to_u64(&current_height_bytes)
+    }
+
+    async fn block_hash(&self, height: u64) -> Result<Vec<u8>, StatusCodeEnum> {
+        // region 4: height -> block hash
+        self.load(4, &height.to_be_bytes()).await
+    }
+
+    /// block hash followed by full block, the format of store_all_block_data
+    async fn load_block_data(&self, height: u64) -> Result<Vec<u8>, StatusCodeEnum> {
+        let mut block_data = self.block_hash(height).await?;
+        block_data.extend_from_slice(&self.load_full_block(height).await?);
+        Ok(block_data)
+    }
+}
+
+impl Storage for DB {
+    async fn load(&self, region: u32, key: &[u8]) -> Result<Vec<u8>, StatusCodeEnum> {
+        DB::load(self, region, key.to_vec())
+    }
+
+    async fn store(&self, region: u32, key: &[u8], value: &[u8]) -> Result<(), StatusCodeEnum> {
+        DB::store(self, region, key.to_vec(), value.to_vec())
+    }
+
+    async fn delete(&self, region: u32, key: &[u8]) -> Result<(), StatusCodeEnum> {
+        DB::delete(self, region, key.to_vec())
+    }
+
+    async fn load_full_block(&self, height: u64) -> Result<Vec<u8>, StatusCodeEnum> {
+        DB::load_full_block(self, height.to_be_bytes().to_vec())
+    }
+
+    async fn set_current_height(&self, height: u64) -> Result<(), StatusCodeEnum> {
+        let compact_block_bytes = DB::load(self, 10, (height + 1).to_be_bytes().to_vec())?;
+        let compact_block = CompactBlock::decode(compact_block_bytes.as_slice()).unwrap();
+        let hash = compact_block.header.unwrap().prevhash;
+
+        // recover current height & hash
+        DB::store(
+            self,
+            0,
+            0u64.to_be_bytes().to_vec(),
+            height.to_be_bytes().to_vec(),
+        )?;
+        DB::store(self, 0, 1u64.to_be_bytes().to_vec(), hash)
+    }
+}
+
+impl Storage for Storager {
+    async fn load(&self, region: u32, key: &[u8]) -> Result<Vec<u8>, StatusCodeEnum> {
+        Storager::load(self, &get_real_key(region, key), true).await
+    }
+
+    async fn store(&self, region: u32, key: &[u8], value: &[u8]) -> Result<(), StatusCodeEnum> {
+        Storager::store(self, &get_real_key(region, key), value).await
+    }
+
+    // delete from the memory cache and the local layer, never touch the cloud layer
+    async fn delete(&self, region: u32, key: &[u8]) -> Result<(), StatusCodeEnum> {
+        let real_key = get_real_key(region, key);
+        // Storager::build always puts the memory cache in front of the local layer
+        for operator in [
+            Some(&self.operator),
+            self.next_storager.as_ref().map(|s| &s.operator),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            operator.delete(&real_key).await.map_err(|e| {
+                println!("delete {} failed: {}", real_key, e);
+                StatusCodeEnum::DeleteError
+            })?;
+        }
+        Ok(())
+    }
+
+    async fn load_full_block(&self, height: u64) -> Result<Vec<u8>, StatusCodeEnum> {
+        Storager::load_full_block(self, &height.to_be_bytes()).await
+    }
+
+    async fn set_current_height(&self, height: u64) -> Result<(), StatusCodeEnum> {
+        // rollback current height and delete height
+        // for storage_opendal current hash is a virtual key, so we only need to rollback current height
+        Storager::store(
+            self,
+            &get_real_key(0, &0u64.to_be_bytes()),
+            &height.to_be_bytes(),
+        )
+        .await?;
+
+        let res = Storager::load(self, &get_real_key(0, &2u64.to_be_bytes()), true).await;
+        if let Ok(delete_height_bytes) = res {
+            let delete_height = bytes_to_u64(&delete_height_bytes);
+            println!("local storage current delete_height: {}", delete_height);
+            let new_delete_height = height.min(delete_height);
+            println!(
+                "local storage delete_height rollback to: {}",
+                new_delete_height
+            );
+
+            Storager::store(
+                self,
+                &get_real_key(0, &2u64.to_be_bytes()),
+                &new_delete_height.to_be_bytes(),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+pub enum StorageDb {
+    RocksDB(DB),
+    Opendal(Storager),
+}
+
+impl Storage for StorageDb {
+    async fn load(&self, region: u32, key: &[u8]) -> Result<Vec<u8>, StatusCodeEnum> {
+        match self {
+            StorageDb::RocksDB(db) => Storage::load(db, region, key).await,
+            StorageDb::Opendal(storager) => Storage::load(storager, region, key).await,
+        }
+    }
+
+    async fn store(&self, region: u32, key: &[u8], value: &[u8]) -> Result<(), StatusCodeEnum> {
+        match self {
+            StorageDb::RocksDB(db) => Storage::store(db, region, key, value).await,
+            StorageDb::Opendal(storager) => Storage::store(storager, region, key, value).await,
+        }
+    }
+
+    async fn delete(&self, region: u32, key: &[u8]) -> Result<(), StatusCodeEnum> {
+        match self {
+            StorageDb::RocksDB(db) => Storage::delete(db, region, key).await,
+            StorageDb::Opendal(storager) => Storage::delete(storager, region, key).await,
+        }
+    }
+
+    async fn load_full_block(&self, height: u64) -> Result<Vec<u8>, StatusCodeEnum> {
+        match self {
+            StorageDb::RocksDB(db) => Storage::load_full_block(db, height).await,
+            StorageDb::Opendal(storager) => Storage::load_full_block(storager, height).await,
+        }
+    }
+
+    async fn set_current_height(&self, height: u64) -> Result<(), StatusCodeEnum> {
+        match self {
+            StorageDb::RocksDB(db) => db.set_current_height(height).await,
+            StorageDb::Opendal(storager) => storager.set_current_height(height).await,
+        }
+    }
+}
+
+/// whether storage_opendal.cloud_storage in config names a service
+pub fn has_cloud_storage(config_path: &Path) -> bool {
+    read_config(config_path)
+        .get("storage_opendal")
+        .and_then(|storage| storage.get("cloud_storage"))
+        .and_then(|cloud_storage| cloud_storage.get("service_type"))
+        .and_then(|service_type| service_type.as_str())
+        .is_some_and(|service_type| !service_type.is_empty())
+}
+
+// layers of storage_opendal from top to bottom
+fn storager_layers(storager: &Storager) -> Vec<&Storager> {
+    let mut layers = Vec::new();
+    let mut layer = Some(storager);
+    while let Some(current) = layer {
+        layers.push(current);
+        layer = current.next_storager.as_deref();
+    }
+    layers
+}
+
+/// the cloud storage layer of storage_opendal, the last layer when cloud_storage is configured
+pub fn cloud_storager<'a>(storager: &'a Storager, config_path: &Path) -> &'a Storager {
+    let layers = storager_layers(storager);
+    if !has_cloud_storage(config_path) || layers.len() < 2 {
+        panic!(
+            "cloud_storage of storage_opendal is not configured in {}",
+            config_path.display()
+        );
+    }
+    layers[layers.len() - 1]
+}
+
+/// the local storage layer of storage_opendal, the last layer above cloud storage
+pub fn local_storager<'a>(storager: &'a Storager, config_path: &Path) -> &'a Storager {
+    let layers = storager_layers(storager);
+    let cloud_layers = usize::from(has_cloud_storage(config_path) && layers.len() >= 2);
+    layers[layers.len() - 1 - cloud_layers]
+}
+
+/// opendal operator of storage_opendal.cloud_storage in config, without building the storage layers
+/// fields are mapped per service the same as Storager::build
+pub fn cloud_operator(config_path: &Path) -> Operator {
+    if !has_cloud_storage(config_path) {
+        panic!(
+            "cloud_storage of storage_opendal is not configured in {}",
+            config_path.display()
+        );
+    }
+    let cloud = OpendalConfig::new(config_path.to_str().unwrap()).cloud_storage;
+    let operator = match cloud.service_type.as_str() {
+        "oss" => {
+            let mut builder = Oss::default();
+            builder
+                .access_key_id(&cloud.access_key_id)
+                .access_key_secret(&cloud.secret_access_key)
+                .endpoint(&cloud.endpoint)
+                .bucket(&cloud.bucket)
+                .root(&cloud.root);
+            Operator::new(builder).map(|builder| builder.finish())
+        }
+        "obs" => {
+            let mut builder = Obs::default();
+            builder
+                .access_key_id(&cloud.access_key_id)
+                .secret_access_key(&cloud.secret_access_key)
+                .endpoint(&cloud.endpoint)
+                .bucket(&cloud.bucket)
+                .root(&cloud.root);
+            Operator::new(builder).map(|builder| builder.finish())
+        }
+        "cos" => {
+            let mut builder = Cos::default();
+            builder
+                .secret_id(&cloud.access_key_id)
+                .secret_key(&cloud.secret_access_key)
+                .endpoint(&cloud.endpoint)
+                .bucket(&cloud.bucket)
+                .root(&cloud.root);
+            Operator::new(builder).map(|builder| builder.finish())
+        }
+        "s3" => {
+            let mut builder = S3::default();
+            builder
+                .access_key_id(&cloud.access_key_id)
+                .secret_access_key(&cloud.secret_access_key)
+                .endpoint(&cloud.endpoint)
+                .bucket(&cloud.bucket)
+                .region(&cloud.region)
+                .root(&cloud.root);
+            Operator::new(builder).map(|builder| builder.finish())
+        }
+        "azblob" => {
+            let mut builder = Azblob::default();
+            builder
+                .account_name(&cloud.access_key_id)
+                .account_key(&cloud.secret_access_key)
+                .endpoint(&cloud.endpoint)
+                .container(&cloud.bucket)
+                .root(&cloud.root);
+            Operator::new(builder).map(|builder| builder.finish())
+        }
+        service_type => panic!("unsupported cloud_storage service_type({})", service_type),
+    };
+    operator
+        .map_err(|e| println!("build cloud_storage operator failed: {e}"))
+        .unwrap()
+}
+
+/// rocksdb dirs of storage data at path
+/// storage_rocksdb db_path is a rocksdb itself, storage_opendal keeps its rocksdb in a sub dir of data_root
+pub fn storage_rocksdb_dirs(path: &Path) -> Vec<PathBuf> {
+    let mut dirs = vec![path.to_path_buf()];
+    if let Ok(entries) = fs::read_dir(path) {
+        dirs.extend(entries.flatten().map(|entry| entry.path()));
+    }
+    dirs.into_iter()
+        .filter(|dir| dir.join("CURRENT").is_file())
+        .collect()
+}
+
+/// local data path of storage
+pub fn storage_path(config_path: &Path) -> String {
+    let config = read_config(config_path);
+    if config.contains_key("storage_rocksdb") {
+        RocksdbConfig::new(config_path.to_str().unwrap()).db_path
+    } else if config.contains_key("storage_opendal") {
+        OpendalConfig::new(config_path.to_str().unwrap()).data_root
+    } else {
+        panic!("storage config not found")
+    }
+}
+
+pub async fn storage_db(config_path: &Path) -> StorageDb {
+    let config = read_config(config_path);
+    if config.contains_key("storage_rocksdb") {
+        let config = RocksdbConfig::new(config_path.to_str().unwrap());
+        StorageDb::RocksDB(DB::new(&config.db_path, &config))
+    } else if config.contains_key("storage_opendal") {
+        let config = OpendalConfig::new(config_path.to_str().unwrap());
+        StorageDb::Opendal(
+            Storager::build(
+                &config.data_root,
+                &config.cloud_storage,
+                &config.exporter,
+                config.l1_capacity,
+                config.l2_capacity,
+            )
+            .await,
+        )
+    } else {
+        panic!("storage config not found")
+    }
+}
+
+/// open storage data copied to path, with the same backend as config
+pub async fn storage_db_at(config_path: &Path, path: &Path) -> StorageDb {
+    let config = read_config(config_path);
+    if config.contains_key("storage_opendal") {
+        let storage_config = OpendalConfig::default();
+        StorageDb::Opendal(
+            Storager::build(
+                path.to_str().unwrap(),
+                &storage_config.cloud_storage,
+                &storage_config.exporter,
+                storage_config.l1_capacity,
+                storage_config.l2_capacity,
+            )
+            .await,
+        )
+    } else if config.contains_key("storage_rocksdb") {
+        let storage_config = RocksdbConfig::default();
+        StorageDb::RocksDB(DB::new(path.to_str().unwrap(), &storage_config))
+    } else {
+        panic!("storage config not found")
+    }
+}
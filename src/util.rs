@@ -12,12 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use cita_database::{Config, DataCategory, Database, RocksDB, NUM_COLUMNS};
+use cita_types::H256;
 use executor_evm::config::ExecutorConfig;
+use executor_evm::types::db_indexes::{BlockNumber2Hash, CurrentHash, DbIndex};
+use executor_evm::types::header::Header;
 use fs_extra::{copy_items, dir::CopyOptions};
 use std::fs;
+use std::io::{stdin, stdout, Write};
 use std::path::Path;
-use storage_opendal::{config::StorageConfig as OpendalConfig, storager::Storager};
-use storage_rocksdb::{config::StorageConfig as RocksdbConfig, db::DB};
+use std::sync::Arc;
 use toml::Table;
 
 pub const HASH_LEN: u32 = 32;
@@ -29,57 +33,67 @@ pub fn get_real_key(region: u32, key: &[u8]) -> String {
     hex::encode([region.to_be_bytes().as_slice(), key].concat())
 }
 
-pub fn executor_db_path(config_path: &Path) -> String {
-    ExecutorConfig::new(config_path.to_str().unwrap()).db_path
-}
-
-pub enum StorageDb {
-    RocksDB(DB),
-    Opendal(Storager),
-}
-
-pub async fn storage_db(config_path: &Path) -> StorageDb {
+pub fn read_config(config_path: &Path) -> Table {
     let s = fs::read_to_string(config_path)
         .map_err(|e| println!("read config err: {e}"))
         .unwrap();
-    let config: Table = s
-        .parse::<Table>()
+    s.parse::<Table>()
         .map_err(|e| println!("config toml parse err: {e}"))
-        .unwrap();
+        .unwrap()
+}
+
+pub fn executor_db_path(config_path: &Path) -> String {
+    ExecutorConfig::new(config_path.to_str().unwrap()).db_path
+}
 
-    if config.contains_key("storage_rocksdb") {
-        let config = RocksdbConfig::new(config_path.to_str().unwrap());
-        StorageDb::RocksDB(DB::new(&config.db_path, &config))
-    } else if config.contains_key("storage_opendal") {
-        let config = OpendalConfig::new(config_path.to_str().unwrap());
-        StorageDb::Opendal(
-            Storager::build(
-                &config.data_root,
-                &config.cloud_storage,
-                &config.exporter,
-                config.l1_capacity,
-                config.l2_capacity,
-            )
-            .await,
-        )
-    } else {
-        panic!("storage config not found")
+pub fn open_state_db(executor_db_path: &str) -> Arc<RocksDB> {
+    let state_path = executor_db_path.to_owned() + "/statedb";
+    if !Path::new(&state_path).exists() {
+        panic!("executor state_db dir not exist");
     }
+    let config = Config::with_category_num(NUM_COLUMNS);
+    Arc::new(RocksDB::open(&state_path, &config).unwrap())
 }
 
-pub async fn read_current_height(storager: &StorageDb) -> u64 {
-    let current_height_bytes = match storager {
-        StorageDb::RocksDB(db) => db.load(0, 0u64.to_be_bytes().to_vec()).unwrap(),
-        StorageDb::Opendal(storager) => storager
-            .load(&get_real_key(0, &0u64.to_be_bytes()), true)
-            .await
-            .unwrap(),
+/// header of the block at height in statedb, or of the current block if height is None
+pub fn state_block_header(state_rocks_db: &RocksDB, height: Option<u64>) -> (H256, Header) {
+    let hash_key = match height {
+        Some(height) => BlockNumber2Hash(height).get_index().to_vec(),
+        None => CurrentHash.get_index().to_vec(),
     };
+    let block_hash = state_rocks_db
+        .get(Some(DataCategory::Extra), &hash_key)
+        .unwrap_or(None)
+        .map(|h| rlp::decode::<H256>(&h).unwrap())
+        .unwrap_or_else(|| panic!("block hash of height({:?}) not found in statedb", height));
+    let block_header = state_rocks_db
+        .get(Some(DataCategory::Headers), block_hash.as_bytes())
+        .unwrap_or(None)
+        .map(|header| rlp::decode::<Header>(header.as_slice()).unwrap())
+        .unwrap_or_else(|| panic!("block header of height({:?}) not found in statedb", height));
+    (block_hash, block_header)
+}
+
+pub fn bytes_to_u64(bytes: &[u8]) -> u64 {
     let mut buf: [u8; 8] = [0; 8];
-    buf.clone_from_slice(&current_height_bytes[..8]);
+    buf.clone_from_slice(&bytes[..8]);
     u64::from_be_bytes(buf)
 }
 
+pub fn parse_hex(s: &str) -> Vec<u8> {
+    hex::decode(s.strip_prefix("0x").unwrap_or(s))
+        .map_err(|e| println!("invalid hex string({s}): {e}"))
+        .unwrap()
+}
+
+pub fn confirm(prompt: &str) -> bool {
+    print!("{} [y/N]: ", prompt);
+    stdout().flush().unwrap();
+    let mut answer = String::new();
+    stdin().read_line(&mut answer).unwrap();
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
 pub fn copy_dir(source_path: &Path, target_path: &Path) {
     if !source_path.exists() {
         panic!("source_path not exist")